@@ -1,7 +1,7 @@
 // Copyright © ArkBig
 //! This file provides application flow.
 
-use std::{cell::RefCell, collections::HashMap, io::IsTerminal as _, rc::Rc};
+use std::{collections::HashMap, io::IsTerminal as _};
 use strum::IntoEnumIterator as _;
 
 /// The application is started and terminated.
@@ -50,17 +50,30 @@ pub fn run() -> proc_exit::ExitResult {
 
         // Input monitoring.
         let is_in_tty = std::io::stdin().is_terminal();
+        let mut paused = false;
         while !updating_thread.is_finished() {
             if is_in_tty && crossterm::event::poll(update_tick_rate).unwrap() {
                 if let crossterm::event::Event::Key(key) = crossterm::event::read().unwrap() {
                     use crossterm::event::{KeyCode, KeyModifiers};
-                    match (key.code, key.modifiers) {
+                    let msg = match (key.code, key.modifiers) {
                         // Cancellation.
                         (KeyCode::Char('c'), KeyModifiers::CONTROL)
-                        | (KeyCode::Char('q'), KeyModifiers::NONE) => {
-                            update_tx.send(UpdateMsg::Quit).unwrap()
+                        | (KeyCode::Char('q'), KeyModifiers::NONE) => Some(UpdateMsg::Quit),
+                        (KeyCode::Char('p') | KeyCode::Char(' '), KeyModifiers::NONE) => {
+                            paused = !paused;
+                            Some(if paused {
+                                UpdateMsg::Pause
+                            } else {
+                                UpdateMsg::Resume
+                            })
                         }
-                        _ => {}
+                        (KeyCode::Char('s'), KeyModifiers::NONE) => Some(UpdateMsg::Skip),
+                        (KeyCode::Char('+'), _) => Some(UpdateMsg::AddRuns(1)),
+                        _ => None,
+                    };
+                    if let Some(msg) = msg {
+                        // The updating thread may have finished meanwhile.
+                        let _ = update_tx.send(msg);
                     }
                 }
             }
@@ -72,6 +85,13 @@ pub fn run() -> proc_exit::ExitResult {
         ret = updating_thread.join().unwrap();
     });
 
+    // Browsing after the inline reports, so they remain after quitting.
+    if let Some(result) = model.write().unwrap().result.take() {
+        if let Err(err) = crate::browser::run(&result) {
+            ret = (proc_exit::Code::FAILURE, Some(format!("{:}", err)));
+        }
+    }
+
     // Exit Code
     let exit_code = ret.0;
     let exit_msg = ret.1;
@@ -145,6 +165,12 @@ fn finalize_cli() {
 /// Messages received by updating thread.
 enum UpdateMsg {
     Quit,
+    /// Pause after the current run.
+    Pause,
+    Resume,
+    /// Go to the next command, keeping the runs so far.
+    Skip,
+    AddRuns(u16),
 }
 
 /// Data model to be updated in the updating thread and viewed in the drawing thread.
@@ -153,6 +179,9 @@ struct SharedViewModel {
     current_run: u16,
     current_max: u16,
     current_reports: Vec<HashMap<crate::cmd::MeasItem, f64>>,
+    paused: bool,
+    /// The result of all commands to browse by `--tui`.
+    result: Option<crate::benchmark::BenchmarkResult>,
 }
 
 /// Updating thread job
@@ -163,223 +192,359 @@ fn run_app(
     model: std::sync::Arc<std::sync::RwLock<SharedViewModel>>,
     cli_args: &crate::cli_args::CliArgs,
 ) -> (proc_exit::Code, Option<String>) {
+    if let Some(crate::cli_args::SubCommand::Import { file }) = &cli_args.subcommand {
+        return import_app(file, draw_tx, model, cli_args);
+    }
+    let mut benchmark = cli_args.to_benchmark();
+    let mut last_tick = std::time::Instant::now();
+    let mut wait_action = || {
+        use crate::benchmark::UserAction;
+        let msg = wait_recv(&rx, tick_rate, last_tick);
+        last_tick = std::time::Instant::now();
+        let mut m = model.write().unwrap();
+        match msg {
+            Some(UpdateMsg::Quit) => UserAction::Quit,
+            Some(UpdateMsg::Pause) => {
+                m.paused = true;
+                UserAction::Pause
+            }
+            Some(UpdateMsg::Resume) => {
+                m.paused = false;
+                UserAction::Resume
+            }
+            Some(UpdateMsg::Skip) => UserAction::Skip,
+            Some(UpdateMsg::AddRuns(runs)) => {
+                m.current_max = m.current_max.saturating_add(runs);
+                UserAction::AddRuns(runs)
+            }
+            None => UserAction::None,
+        }
+    };
+    let mut wait = || wait_action() == crate::benchmark::UserAction::Quit;
+
     // Checking available
-    let time_commands = prepare_time_commands(&rx, tick_rate, cli_args);
-    if time_commands.is_none() {
+    let backends = benchmark.prepare_backends(&mut wait);
+    if backends.is_none() {
         // quit
         return (proc_exit::Code::FAILURE, None);
     }
-    let time_commands = time_commands.unwrap();
-    if time_commands.is_empty() {
+    let mut backends = backends.unwrap();
+    if backends.is_empty() {
         return (
             proc_exit::Code::FAILURE,
-            Some(String::from(
-                "time command not found. Install the BSD or GNU version or both.",
-            )),
+            Some(benchmark.no_backend_message()),
         );
     }
-    if !cli_args.use_builtin_only {
-        if !cli_args.no_bsd
-            && !time_commands
-                .iter()
-                .any(|x| x.borrow().cmd_type == crate::cmd::CmdType::Bsd)
-        {
-            draw_tx.send(DrawMsg::Warn("The bsd time command not found. Please install or specify `--no-bsd` to turn off this warning.".to_string())).unwrap();
+    let found = |name: &str| backends.iter().any(|x| x.name() == name);
+    match benchmark.backend {
+        crate::benchmark::Backend::Auto => {
+            if found("builtin") {
+                draw_tx.send(DrawMsg::Warn("No backend other than built-in is available, so memory-related items may not be measured. Please install the BSD or GNU time, or specify `--backend builtin` to turn off this warning.".to_string())).unwrap();
+            }
         }
-        if !cli_args.no_gnu
-            && !time_commands
-                .iter()
-                .any(|x| x.borrow().cmd_type == crate::cmd::CmdType::Gnu)
-        {
-            draw_tx.send(DrawMsg::Warn("The gnu time command not found. Please install or specify `--no-gnu=` to turn off this warning.".to_string())).unwrap();
+        crate::benchmark::Backend::Native
+        | crate::benchmark::Backend::Gnu
+        | crate::benchmark::Backend::Bsd
+        | crate::benchmark::Backend::Busybox => {
+            use clap::ValueEnum as _;
+            let name = benchmark.backend.to_possible_value().unwrap();
+            if !found(name.get_name()) {
+                draw_tx.send(DrawMsg::Warn(format!("The {} time command not found. Please install or choose another `--backend` to turn off this warning.", name.get_name()))).unwrap();
+            }
+        }
+        crate::benchmark::Backend::AllAlternating => {
+            // Same order as prepare_backends.
+            for name in ["bsd", "gnu", "busybox", "native"] {
+                if !found(name) {
+                    draw_tx.send(DrawMsg::Warn(format!("The {} time command not found. Only the available backends are used alternately.", name))).unwrap();
+                }
+            }
+        }
+        crate::benchmark::Backend::Builtin => {}
+    }
+    // The settings are printed in the metadata.
+    if let Err(err) = crate::sched::check(
+        &benchmark.shell,
+        &benchmark.shell_arg,
+        &benchmark.exec_options(0),
+    ) {
+        return (proc_exit::Code::FAILURE, Some(format!("{:}", err)));
+    }
+    for path in std::mem::take(&mut benchmark.cold_cache) {
+        match crate::cache::evict(std::slice::from_ref(&path)) {
+            Ok(()) => benchmark = benchmark.cold_cache(path),
+            Err(err) => draw_tx
+                .send(DrawMsg::Warn(format!(
+                    "{:} So it is not evicted before each run.",
+                    err
+                )))
+                .unwrap(),
+        }
+    }
+    if benchmark.drop_caches {
+        if let Err(err) = crate::cache::drop_caches() {
+            draw_tx.send(DrawMsg::Warn(format!("{:} So the page cache is not dropped. Please run as root, or use `--cold-cache` to evict only the specified files.", err))).unwrap();
+            benchmark = benchmark.drop_caches(false);
+        }
+    }
+    if benchmark.perf_counters {
+        if let Err(err) = crate::perf::check() {
+            draw_tx.send(DrawMsg::Warn(format!("Hardware performance counters may not be measured. {:}. Check `/proc/sys/kernel/perf_event_paranoid`, or the PMU support of the VM.", err))).unwrap();
+        }
+    }
+    if benchmark.uses_cgroup() {
+        match crate::cgroup::Cgroup::create(benchmark.memory_limit, benchmark.cpu_quota) {
+            Ok(cgroup) => drop(cgroup),
+            Err(err) if benchmark.memory_limit.is_none() && benchmark.cpu_quota.is_none() => {
+                draw_tx
+                    .send(DrawMsg::Warn(format!(
+                        "The cgroup could not be created, so it runs without the cgroup. {:}",
+                        err
+                    )))
+                    .unwrap();
+            }
+            Err(err) => return (proc_exit::Code::FAILURE, Some(format!("{:}", err))),
         }
     }
 
-    // Benchmarking
-    let mut last_tick = std::time::Instant::now();
-    for (target_index, target) in cli_args.normalized_commands().iter().enumerate() {
+    let tui = tui_enabled(cli_args, &draw_tx);
+    if !cli_args.no_system_check {
+        for warning in crate::system::SystemState::capture().warnings(true) {
+            draw_tx.send(DrawMsg::Warn(warning)).unwrap();
+        }
+    }
+
+    let targets = benchmark.commands();
+    if targets.len() < cli_args.names.len() {
         draw_tx
-            .send(DrawMsg::PrintH(format!(
-                "Benchmark #{}> {}",
-                target_index + 1,
-                target
+            .send(DrawMsg::Warn(format!(
+                "{} labels are specified by `--name` for {} commands. The extra labels are ignored.",
+                cli_args.names.len(),
+                targets.len()
             )))
             .unwrap();
-        {
-            let mut m = model.write().unwrap();
-            m.current_reports = Vec::new();
-            m.current_max = cli_args.runs;
-            draw_tx.send(DrawMsg::StartMeasure).unwrap();
-        }
-        for n in 0..cli_args.runs {
-            model.write().unwrap().current_run = n;
-            let time_cmd = Rc::clone(&time_commands[(n as usize) % time_commands.len()]);
-            let mut running = false;
-            loop {
-                if running {
-                    if (*time_cmd).borrow_mut().is_finished() {
-                        model
-                            .write()
-                            .unwrap()
-                            .current_reports
-                            .push((*time_cmd).borrow_mut().get_report().unwrap().clone());
-                        break;
-                    }
-                } else {
-                    let time_cmd_result = if cli_args.loops <= 1 {
-                        (*time_cmd).borrow_mut().execute(target.as_str())
-                    } else {
-                        (*time_cmd).borrow_mut().execute(
-                            format!(
-                                "sh -c 'for i in {} ;do {};done'",
-                                vec!["0"; cli_args.loops as usize].join(" "),
-                                target
-                            )
-                            .as_str(),
-                        )
-                    };
-                    if let Err(err) = time_cmd_result {
-                        return (proc_exit::Code::FAILURE, Some(format!("{:}", err)));
-                    }
-                    running = true;
-                }
-                if wait_recv_quit(&rx, tick_rate, last_tick) {
-                    if running {
-                        (*time_cmd).borrow_mut().kill().unwrap();
-                    }
-                    return (proc_exit::Code::FAILURE, None);
-                }
-                last_tick = std::time::Instant::now();
-            }
-        }
+    }
+    if targets.len() < cli_args.max_command_index() {
         draw_tx
-            .send(DrawMsg::ReportMeasure(
-                model.read().unwrap().current_reports.clone(),
-            ))
+            .send(DrawMsg::Warn(format!(
+                "Command #{} is specified by `--command-env` or `--command-cwd`, but there are only {} commands.",
+                cli_args.max_command_index(),
+                targets.len()
+            )))
             .unwrap();
     }
+
+    let mut reporters: Vec<Box<dyn crate::reporter::Reporter>> = vec![Box::new(TerminalReporter {
+        draw_tx: draw_tx.clone(),
+        model: model.clone(),
+        runs: benchmark.runs,
+        system_check: !cli_args.no_system_check,
+    })];
+    match export_reporters(cli_args) {
+        Ok(mut exports) => reporters.append(&mut exports),
+        Err(err) => return (proc_exit::Code::FAILURE, Some(format!("{:}", err))),
+    }
+
+    // Benchmarking
+    match benchmark.run_reporting(&mut backends, &mut reporters, &mut wait_action) {
+        Ok(Some(result)) => {
+            // Distinct from a failure, as a shell does for SIGINT.
+            let code = if result.interrupted {
+                proc_exit::bash::SIGINT
+            } else {
+                proc_exit::Code::SUCCESS
+            };
+            if tui {
+                model.write().unwrap().result = Some(result);
+            }
+            (code, None)
+        }
+        Ok(None) => (proc_exit::Code::FAILURE, None),
+        Err(err) => (proc_exit::Code::FAILURE, Some(format!("{:}", err))),
+    }
+}
+
+/// Reporters of the export options, in the order of the options.
+fn export_reporters(
+    cli_args: &crate::cli_args::CliArgs,
+) -> anyhow::Result<Vec<Box<dyn crate::reporter::Reporter>>> {
+    let mut reporters: Vec<Box<dyn crate::reporter::Reporter>> = Vec::new();
+    if let Some(path) = &cli_args.export_json {
+        reporters.push(Box::new(crate::reporter::JsonReporter::create(path)?));
+    }
+    if let Some(path) = &cli_args.export_csv {
+        reporters.push(Box::new(crate::reporter::CsvReporter::create(path)?));
+    }
+    if let Some(path) = &cli_args.export_html {
+        reporters.push(Box::new(crate::html::HtmlReporter::create(path)?));
+    }
+    if let Some(dir) = &cli_args.export_gnuplot {
+        reporters.push(Box::new(crate::plot::GnuplotReporter::create(dir)?));
+    }
+    if let Some(path) = &cli_args.export_vega {
+        reporters.push(Box::new(crate::plot::VegaReporter::create(path)?));
+    }
+    if let Some(path) = &cli_args.export_hyperfine_json {
+        reporters.push(Box::new(crate::hyperfine::HyperfineReporter::create(path)?));
+    }
+    Ok(reporters)
+}
+
+/// Report the imported results instead of benchmarking, by `mntime import`.
+fn import_app(
+    path: &std::path::Path,
+    draw_tx: std::sync::mpsc::Sender<DrawMsg>,
+    model: std::sync::Arc<std::sync::RwLock<SharedViewModel>>,
+    cli_args: &crate::cli_args::CliArgs,
+) -> (proc_exit::Code, Option<String>) {
+    let result = match crate::hyperfine::import(path) {
+        Ok(result) => result,
+        Err(err) => return (proc_exit::Code::FAILURE, Some(format!("{:#}", err))),
+    };
+    let tui = tui_enabled(cli_args, &draw_tx);
+    draw_tx
+        .send(DrawMsg::PrintH(format!("Import> {}", path.display())))
+        .unwrap();
+    let mut reporters: Vec<Box<dyn crate::reporter::Reporter>> = vec![Box::new(TerminalReporter {
+        draw_tx: draw_tx.clone(),
+        model: model.clone(),
+        runs: result
+            .commands
+            .iter()
+            .map(|x| x.runs.len() as u16)
+            .max()
+            .unwrap_or(0),
+        system_check: false,
+    })];
+    match export_reporters(cli_args) {
+        Ok(mut exports) => reporters.append(&mut exports),
+        Err(err) => return (proc_exit::Code::FAILURE, Some(format!("{:}", err))),
+    }
+    if let Err(err) = crate::reporter::replay(&result, &mut reporters) {
+        return (proc_exit::Code::FAILURE, Some(format!("{:}", err)));
+    }
+    if tui {
+        model.write().unwrap().result = Some(result);
+    }
     (proc_exit::Code::SUCCESS, None)
 }
 
-fn wait_recv_quit(
-    rx: &std::sync::mpsc::Receiver<UpdateMsg>,
-    tick_rate: std::time::Duration,
-    last_tick: std::time::Instant,
+/// `--tui` is enabled only in the terminal, otherwise it is warned.
+fn tui_enabled(
+    cli_args: &crate::cli_args::CliArgs,
+    draw_tx: &std::sync::mpsc::Sender<DrawMsg>,
 ) -> bool {
-    let timeout = tick_rate
-        .checked_sub(last_tick.elapsed())
-        .unwrap_or_else(|| std::time::Duration::from_secs(0));
-    let msg = rx.recv_timeout(timeout);
-    matches!(msg, Ok(UpdateMsg::Quit))
+    let tui = cli_args.tui && std::io::stdin().is_terminal() && std::io::stdout().is_terminal();
+    if cli_args.tui && !tui {
+        draw_tx
+            .send(DrawMsg::Warn(String::from(
+                "`--tui` is ignored, because stdin or stdout is not a terminal.",
+            )))
+            .unwrap();
+    }
+    tui
 }
 
-/// Checks and returns the time command to be used.
-///
-/// The default is to try to run BSD and GNU alternately.
-/// If neither of those is available, use built-in.
-fn prepare_time_commands(
-    rx: &std::sync::mpsc::Receiver<UpdateMsg>,
-    tick_rate: std::time::Duration,
-    cli_args: &crate::cli_args::CliArgs,
-) -> Option<Vec<Rc<RefCell<crate::cmd::TimeCmd>>>> {
-    let mut commands = Vec::<_>::new();
-    if !cli_args.use_builtin_only {
-        if !cli_args.no_bsd {
-            let mut fallback_sh = false;
-            loop {
-                let mut cmd = crate::cmd::try_new_bsd_time(cli_args, fallback_sh);
-                match command_available(rx, tick_rate, &mut cmd) {
-                    None => return None,
-                    Some(available) => {
-                        if available {
-                            commands.push(Rc::new(RefCell::new(cmd.unwrap())));
-                            break;
-                        } else if fallback_sh {
-                            break;
-                        } else {
-                            fallback_sh = true;
-                        }
-                    }
-                }
-            }
-        }
-        if !cli_args.no_gnu {
-            let mut fallback_sh = false;
-            let mut fallback_time = false;
-            loop {
-                let mut cmd = crate::cmd::try_new_gnu_time(cli_args, fallback_sh, fallback_time);
-                match command_available(rx, tick_rate, &mut cmd) {
-                    None => return None,
-                    Some(available) => {
-                        if available {
-                            commands.push(Rc::new(RefCell::new(cmd.unwrap())));
-                            break;
-                        } else if fallback_sh && fallback_time {
-                            break;
-                        } else if fallback_sh {
-                            fallback_time = true;
-                        } else if fallback_time {
-                            fallback_sh = true;
-                            fallback_time = false;
-                        } else {
-                            fallback_time = true;
-                        }
-                    }
-                }
-            }
+/// Reports to the terminal via the drawing thread.
+struct TerminalReporter {
+    draw_tx: std::sync::mpsc::Sender<DrawMsg>,
+    model: std::sync::Arc<std::sync::RwLock<SharedViewModel>>,
+    runs: u16,
+    system_check: bool,
+}
+
+impl crate::reporter::Reporter for TerminalReporter {
+    fn all_start(&mut self, metadata: &crate::metadata::Metadata) -> anyhow::Result<()> {
+        self.draw_tx.send(DrawMsg::PrintH(format!(
+            "Metadata> mntime {} at {}",
+            metadata.version, metadata.timestamp
+        )))?;
+        for (label, value) in metadata.lines() {
+            self.draw_tx
+                .send(DrawMsg::Print(format!("{:>8}: {}", label, value)))?;
         }
+        Ok(())
     }
-    if commands.is_empty() {
-        let mut fallback_sh = false;
-        loop {
-            let mut cmd = crate::cmd::try_new_builtin_time(cli_args, fallback_sh);
-            match command_available(rx, tick_rate, &mut cmd) {
-                None => return None,
-                Some(available) => {
-                    if available {
-                        commands.push(Rc::new(RefCell::new(cmd.unwrap())));
-                        break;
-                    } else if fallback_sh {
-                        break;
-                    } else {
-                        fallback_sh = true;
-                    }
-                }
-            }
-        }
+
+    fn benchmark_start(
+        &mut self,
+        index: usize,
+        command: &crate::benchmark::BenchmarkCommand,
+    ) -> anyhow::Result<()> {
+        self.draw_tx.send(DrawMsg::PrintH(format!(
+            "Benchmark #{}> {}",
+            index + 1,
+            command.display_name()
+        )))?;
+        let mut m = self.model.write().unwrap();
+        m.current_reports = Vec::new();
+        m.current_run = 0;
+        m.current_max = self.runs;
+        self.draw_tx.send(DrawMsg::StartMeasure)?;
+        Ok(())
     }
-    Some(commands)
-}
 
-/// Check if the specified time command is available.
-fn command_available(
-    rx: &std::sync::mpsc::Receiver<UpdateMsg>,
-    tick_rate: std::time::Duration,
-    command: &mut anyhow::Result<crate::cmd::TimeCmd>,
-) -> Option<bool> {
-    if command.is_err() {
-        return Some(false);
+    fn run_finished(
+        &mut self,
+        _index: usize,
+        n: u16,
+        _backend: &str,
+        report: &HashMap<crate::cmd::MeasItem, f64>,
+    ) -> anyhow::Result<()> {
+        let mut m = self.model.write().unwrap();
+        m.current_reports.push(report.clone());
+        m.current_run = n + 1;
+        Ok(())
     }
-    let mut last_tick = std::time::Instant::now();
-    let cmd = command.as_mut().unwrap();
-    loop {
-        match cmd.ready_status() {
-            crate::cmd::ReadyStatus::Checking => {}
-            crate::cmd::ReadyStatus::Ready => {
-                return Some(true);
-            }
-            crate::cmd::ReadyStatus::Error => {
-                return Some(false);
+
+    fn command_finished(
+        &mut self,
+        index: usize,
+        result: &crate::benchmark::CommandResult,
+    ) -> anyhow::Result<()> {
+        self.draw_tx
+            .send(DrawMsg::ReportMeasure(Box::new(result.clone())))?;
+        if result.partial {
+            self.draw_tx.send(DrawMsg::Warn(format!(
+                "Benchmark #{} was stopped after {} runs, so the results are partial.",
+                index + 1,
+                result.runs.len()
+            )))?;
+        }
+        if let Some(after) = result.system_after.as_ref().filter(|_| self.system_check) {
+            for warning in after.warnings(false) {
+                self.draw_tx.send(DrawMsg::Warn(format!(
+                    "While benchmarking #{}: {}",
+                    index + 1,
+                    warning
+                )))?;
             }
         }
+        Ok(())
+    }
 
-        if wait_recv_quit(rx, tick_rate, last_tick) {
-            return None;
+    fn all_finished(&mut self, result: &crate::benchmark::BenchmarkResult) -> anyhow::Result<()> {
+        if result.interrupted {
+            self.draw_tx.send(DrawMsg::Warn(String::from(
+                "Interrupted, so the remaining commands are not run and the results are incomplete.",
+            )))?;
         }
-        last_tick = std::time::Instant::now();
+        Ok(())
     }
 }
 
+/// Wait until the next tick, and return the message received meanwhile.
+fn wait_recv(
+    rx: &std::sync::mpsc::Receiver<UpdateMsg>,
+    tick_rate: std::time::Duration,
+    last_tick: std::time::Instant,
+) -> Option<UpdateMsg> {
+    let timeout = tick_rate
+        .checked_sub(last_tick.elapsed())
+        .unwrap_or_else(|| std::time::Duration::from_secs(0));
+    rx.recv_timeout(timeout).ok()
+}
+
 //=============================================================================
 // Drawing
 //=============================================================================
@@ -389,8 +554,9 @@ enum DrawMsg {
     Quit,
     Warn(String),
     PrintH(String),
+    Print(String),
     StartMeasure,
-    ReportMeasure(Vec<HashMap<crate::cmd::MeasItem, f64>>),
+    ReportMeasure(Box<crate::benchmark::CommandResult>),
 }
 
 // Drawing thread state.
@@ -443,13 +609,35 @@ fn view_app<B>(
                 terminal.flush(true);
                 CONTINUE_TIME.store(true, std::sync::atomic::Ordering::Relaxed);
             }
+            Ok(DrawMsg::Print(text)) => {
+                terminal.clear_after();
+                terminal.queue_print(crossterm::style::Print(text + "\r\n"));
+                terminal.flush(true);
+            }
             Ok(DrawMsg::StartMeasure) => {
                 draw_state.measuring = true;
             }
-            Ok(DrawMsg::ReportMeasure(reports)) => {
+            Ok(DrawMsg::ReportMeasure(result)) => {
                 draw_state.measuring = false;
                 terminal.clear_after();
-                print_reports(terminal, reports.as_ref(), cli_args.loops);
+                let results = result.by_backend();
+                if results.len() <= 1 {
+                    print_reports(terminal, result.runs.as_ref(), cli_args.loops);
+                    print_histograms(terminal, &result, &cli_args.histogram);
+                } else {
+                    // Report separately, because items of different backends have different semantics.
+                    for result in results {
+                        terminal.queue_attribute(crossterm::style::Attribute::Bold);
+                        terminal.queue_print(crossterm::style::Print(format!(
+                            "[{}] {} runs\r\n",
+                            result.backends[0],
+                            result.runs.len()
+                        )));
+                        terminal.flush(true);
+                        print_reports(terminal, result.runs.as_ref(), cli_args.loops);
+                        print_histograms(terminal, &result, &cli_args.histogram);
+                    }
+                }
             }
             _ => {}
         }
@@ -484,6 +672,7 @@ fn ui(
     if state.measuring {
         _offset_y += draw_progress(f, model, state, cur_y, _offset_y, loops);
         _offset_y += draw_summary_report(f, model, state, cur_y, _offset_y, loops);
+        _offset_y += draw_live_chart(f, model, state, cur_y, _offset_y, loops);
     }
 }
 
@@ -524,7 +713,9 @@ fn draw_progress(
         .use_type(throbber_widgets_tui::WhichUse::Spin);
     f.render_stateful_widget(throbber, chunks[0], &mut state.throbber);
 
-    let label = if model.current_reports.is_empty() {
+    let label = if model.paused {
+        String::from("Paused after this run. Press p to resume.")
+    } else if model.current_reports.is_empty() {
         String::from("Measuring...")
     } else {
         let samples: Vec<_> = model
@@ -613,6 +804,91 @@ fn draw_summary_report(
     height
 }
 
+/// Sparklines of the runs so far, to see if the timings are drifting or bimodal while measuring.
+fn draw_live_chart(
+    f: &mut ratatui::Frame,
+    model: &SharedViewModel,
+    _state: &mut DrawState,
+    cur_y: &mut u16,
+    offset_y: u16,
+    loops: u16,
+) -> u16 {
+    use crate::cmd::{meas_item_unit_value, MeasItem};
+
+    let items: Vec<_> = [MeasItem::Real, MeasItem::MaxResident]
+        .into_iter()
+        .filter(|x| model.current_reports.iter().any(|r| r.contains_key(x)))
+        .collect();
+    let size = f.size();
+    let height = items.len() as u16;
+    if height == 0 || size.height < offset_y + height {
+        return 0;
+    }
+    while size.height < *cur_y + offset_y + height {
+        println!();
+        *cur_y -= 1;
+    }
+
+    for (index, item) in items.iter().enumerate() {
+        let rect = ratatui::layout::Rect::new(0, *cur_y + offset_y + index as u16, size.width, 1);
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Horizontal)
+            .constraints(
+                [
+                    ratatui::layout::Constraint::Length(40),
+                    ratatui::layout::Constraint::Percentage(100),
+                ]
+                .as_ref(),
+            )
+            .split(rect);
+        let samples: Vec<_> = model
+            .current_reports
+            .iter()
+            .filter_map(|x| x.get(item))
+            .copied()
+            .collect();
+        let stats = crate::stats::Stats::new(&samples);
+        let text = ratatui::widgets::Paragraph::new(ratatui::text::Line::from(format!(
+            "{} {} ≦ {}",
+            item.as_ref(),
+            meas_item_unit_value(item, stats.min(), loops),
+            meas_item_unit_value(item, stats.max(), loops),
+        )));
+        f.render_widget(text, chunks[0]);
+        let data = sparkline_data(&samples, chunks[1].width as usize);
+        let sparkline = ratatui::widgets::Sparkline::default()
+            .data(&data)
+            .max(SPARKLINE_MAX)
+            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+        f.render_widget(sparkline, chunks[1]);
+    }
+
+    height
+}
+
+/// The top of the sparkline, which has 8 levels in one line.
+const SPARKLINE_MAX: u64 = 8;
+
+/// Scale the last `width` samples from min to max into 1..=SPARKLINE_MAX.
+///
+/// It is not from zero, because the difference between runs is usually small compared to the values.
+fn sparkline_data(samples: &[f64], width: usize) -> Vec<u64> {
+    let samples = &samples[samples.len().saturating_sub(width)..];
+    let min = samples.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = samples.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    samples
+        .iter()
+        .map(|&x| {
+            if min < max {
+                1 + ((x - min) / (max - min) * (SPARKLINE_MAX - 1) as f64).round() as u64
+            } else {
+                // Flat in the middle if all the same.
+                SPARKLINE_MAX / 2
+            }
+        })
+        .collect()
+}
+
 fn print_reports<B>(
     terminal: &mut crate::terminal::Wrapper<B>,
     reports: &[HashMap<crate::cmd::MeasItem, f64>],
@@ -624,9 +900,19 @@ fn print_reports<B>(
 
     const MEAN_WIDTH: usize = 13;
 
+    let mut metrics: Vec<_> = reports
+        .iter()
+        .flat_map(|x| x.keys())
+        .filter(|x| matches!(x, crate::cmd::MeasItem::Metric(..)))
+        .cloned()
+        .collect();
+    metrics.sort_by_key(crate::cmd::meas_item_key);
+    metrics.dedup();
+    let name_width = meas_item_name_max_width(&metrics, loops);
+
     let mut lines = Vec::new();
     let mut exist_error = false;
-    for item in crate::cmd::MeasItem::iter() {
+    for item in crate::cmd::MeasItem::iter().chain(metrics) {
         let samples: Vec<_> = reports
             .iter()
             .filter_map(|x| x.get(&item))
@@ -636,6 +922,12 @@ fn print_reports<B>(
             crate::cmd::MeasItem::Real | crate::cmd::MeasItem::User | crate::cmd::MeasItem::Sys => {
                 // Required.
             }
+            crate::cmd::MeasItem::Metric(..) => {
+                // Zero is also a valid value for user-defined ones.
+                if samples.is_empty() {
+                    continue;
+                }
+            }
             _ => {
                 // Skip if can't measure.
                 if !samples.iter().any(|&x| x.to_bits() != 0) {
@@ -648,7 +940,7 @@ fn print_reports<B>(
         }
         if item == crate::cmd::MeasItem::ExitStatus {
             exist_error = true;
-            print_exit_status(terminal, &samples, loops);
+            print_exit_status(terminal, &samples, loops, name_width);
             continue;
         }
         let stats = crate::stats::Stats::new(&samples);
@@ -662,7 +954,7 @@ fn print_reports<B>(
             meas_item_unit_value(&item, stats.median(), loops),
             meas_item_unit_value(&item, stats.max(), loops),
             stats.count(),
-            name_width = meas_item_name_max_width(loops),
+            name_width = name_width,
             mean_width = MEAN_WIDTH,
         ));
         if stats.has_outlier() {
@@ -677,7 +969,7 @@ fn print_reports<B>(
                 meas_item_unit_value(&item, stats.max_excluding_outlier(), loops),
                 stats.count_excluding_outlier(),
                 stats.outlier_count,
-                name_width = meas_item_name_max_width(loops),
+                name_width = name_width,
                 mean_width = MEAN_WIDTH,
             ));
         }
@@ -692,7 +984,7 @@ fn print_reports<B>(
         "{:^name_width$}:{:>mean_width$} ± σ (Coefficient of variation %) [Min ≦ Median ≦ Max] / Valid count\r\n",
         "LEGEND",
         "Mean",
-        name_width = meas_item_name_max_width(loops),
+        name_width = name_width,
         mean_width = MEAN_WIDTH,
     )));
     terminal.queue_attribute(crossterm::style::Attribute::Reset);
@@ -701,11 +993,37 @@ fn print_reports<B>(
     terminal.flush(true);
 }
 
-fn print_exit_status<B>(terminal: &mut crate::terminal::Wrapper<B>, samples: &[f64], loops: u16)
-where
+/// Print the histograms of the items whose keys are specified by `--histogram`.
+fn print_histograms<B>(
+    terminal: &mut crate::terminal::Wrapper<B>,
+    result: &crate::benchmark::CommandResult,
+    keys: &[String],
+) where
+    B: ratatui::backend::Backend,
+{
+    let width = crossterm::terminal::size()
+        .map(|x| x.0 as usize)
+        .unwrap_or(80);
+    for item in result
+        .items()
+        .iter()
+        .filter(|x| keys.contains(&crate::cmd::meas_item_key(x)))
+    {
+        let lines = crate::histogram::lines(item, &result.samples(item), result.loops, width);
+        terminal.queue_print(crossterm::style::Print(lines.join("\r\n") + "\r\n"));
+    }
+    terminal.flush(true);
+}
+
+fn print_exit_status<B>(
+    terminal: &mut crate::terminal::Wrapper<B>,
+    samples: &[f64],
+    loops: u16,
+    name_width: usize,
+) where
     B: ratatui::backend::Backend,
 {
-    use crate::cmd::{meas_item_name, meas_item_name_max_width};
+    use crate::cmd::meas_item_name;
 
     let mut histogram = samples.iter().fold(HashMap::<i32, i16>::new(), |mut s, x| {
         let code = x.floor() as i32;
@@ -717,9 +1035,7 @@ where
         s
     });
     let success = *histogram.get(&0).unwrap_or(&0);
-    if histogram.get(&0).is_some() {
-        histogram.remove(&0);
-    }
+    histogram.remove(&0);
     let failure = samples.len() - success as usize;
     let mut failure_codes = histogram.iter().collect::<Vec<_>>();
     failure_codes.sort_by(|a, b| a.0.cmp(b.0));
@@ -727,7 +1043,7 @@ where
     terminal.queue_print(crossterm::style::Print(format!(
         "{:>name_width$}: ",
         meas_item_name(&crate::cmd::MeasItem::ExitStatus, loops),
-        name_width = meas_item_name_max_width(loops)
+        name_width = name_width
     )));
     terminal.queue_fg(crossterm::style::Color::Green);
     terminal.queue_print(crossterm::style::Print(format!(
@@ -747,3 +1063,60 @@ where
     terminal.queue_attribute(crossterm::style::Attribute::Reset);
     terminal.flush(true);
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn app_sparkline_data() {
+        assert_eq!(sparkline_data(&[1.0, 2.0, 3.0], 10), vec![1, 5, 8]);
+        // Only the last ones that fit.
+        assert_eq!(sparkline_data(&[9.0, 1.0, 2.0, 3.0], 3), vec![1, 5, 8]);
+        assert_eq!(sparkline_data(&[2.0, 2.0], 10), vec![4, 4]);
+        assert_eq!(sparkline_data(&[2.0], 10), vec![4]);
+        assert!(sparkline_data(&[], 10).is_empty());
+        assert!(sparkline_data(&[1.0, 2.0], 0).is_empty());
+    }
+
+    #[test]
+    fn app_draw_live_chart() {
+        use crate::cmd::MeasItem;
+
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(60, 4)).unwrap();
+        let mut state = DrawState::default();
+        let mut draw = |model: &SharedViewModel| {
+            let mut height = 0;
+            terminal
+                .draw(|f| height = draw_live_chart(f, model, &mut state, &mut 0, 1, 1))
+                .unwrap();
+            height
+        };
+        // Nothing to draw before the first run.
+        assert_eq!(draw(&SharedViewModel::default()), 0);
+
+        // Only the items measured so far.
+        let model = SharedViewModel {
+            current_reports: [1.0, 2.0, 3.0]
+                .iter()
+                .map(|&x| HashMap::from([(MeasItem::Real, x)]))
+                .collect(),
+            ..Default::default()
+        };
+        assert_eq!(draw(&model), 1);
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|line| line.iter().map(|x| x.symbol()).collect())
+            .collect();
+        assert_eq!(lines[0].trim(), "");
+        // The range in the first 40 columns, and the sparkline scaled from min to max.
+        assert_eq!(
+            lines[1].trim_end(),
+            format!("{:<40}▁▅█", "Real 1 sec ≦ 3 sec")
+        );
+        assert_eq!(lines[2].trim(), "");
+    }
+}
@@ -0,0 +1,1051 @@
+// Copyright © ArkBig
+//! This file provides the benchmark settings and the measurement flow without any drawing.
+
+use std::collections::HashMap;
+use strum::IntoEnumIterator as _;
+
+use crate::cmd::{BackendFactory, ExecOptions, MeasItem, MeasureBackend};
+use crate::metadata::Metadata;
+use crate::metric::Metric;
+use crate::proc_sampler::MemorySample;
+use crate::reporter::Reporter;
+use crate::sched::SchedPolicy;
+use crate::system::SystemState;
+
+/// Backend to measure with.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, clap::ValueEnum)]
+pub enum Backend {
+    /// Use the first available one of BSD, GNU, BusyBox, native and built-in.
+    #[default]
+    Auto,
+    /// rusage of wait4(2) without any time command. Only on Unix.
+    Native,
+    /// GNU time `gtime -v`.
+    Gnu,
+    /// BSD time `time -l`.
+    Bsd,
+    /// BusyBox time `busybox time -v`, mainly on Alpine Linux.
+    Busybox,
+    /// The shell built-in time, which measures only real, user and sys.
+    Builtin,
+    /// Use all available ones of BSD, GNU, BusyBox and native alternately, and report them separately.
+    AllAlternating,
+}
+
+/// One command to benchmark.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BenchmarkCommand {
+    /// Command line executed by the shell.
+    pub command: String,
+    /// Label shown instead of the command line.
+    pub name: Option<String>,
+    /// Options only for this command. They take precedence over Benchmark's.
+    pub options: ExecOptions,
+}
+
+impl BenchmarkCommand {
+    pub fn new(command: impl Into<String>) -> Self {
+        Self {
+            command: command.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the label shown instead of the command line.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Add an environment variable only for this command.
+    pub fn env(mut self, key: impl Into<String>, val: impl Into<String>) -> Self {
+        self.options.envs.push((key.into(), val.into()));
+        self
+    }
+
+    /// Set the working directory only for this command.
+    pub fn cwd(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.options.cwd = Some(dir.into());
+        self
+    }
+
+    /// The label, or the command line if not labeled.
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.command)
+    }
+}
+
+/// Benchmark settings, and the entry point for measuring from Rust code.
+///
+/// ```no_run
+/// let result = mntime_lib::Benchmark::new()
+///     .command("sleep 0.1")
+///     .command(mntime_lib::BenchmarkCommand::new("sleep 0.2").name("longer"))
+///     .runs(5)
+///     .run()
+///     .unwrap();
+/// for command in &result.commands {
+///     let stats = command.stats(&mntime_lib::MeasItem::Real);
+///     println!("{}: {} ± {}", command.name, stats.mean, stats.stdev);
+/// }
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Benchmark {
+    pub(crate) commands: Vec<BenchmarkCommand>,
+    pub(crate) runs: u16,
+    pub(crate) loops: u16,
+    pub(crate) backend: Backend,
+    pub(crate) shell: String,
+    pub(crate) shell_arg: String,
+    pub(crate) builtin: String,
+    pub(crate) bsd: String,
+    pub(crate) gnu: String,
+    pub(crate) busybox: String,
+    pub(crate) options: ExecOptions,
+    pub(crate) metrics: Vec<Metric>,
+    pub(crate) sample_memory: bool,
+    pub(crate) process_tree: bool,
+    pub(crate) cgroup: bool,
+    pub(crate) memory_limit: Option<u64>,
+    pub(crate) cpu_quota: Option<f64>,
+    pub(crate) perf_counters: bool,
+    pub(crate) proc_io: bool,
+    pub(crate) cold_cache: Vec<std::path::PathBuf>,
+    pub(crate) drop_caches: bool,
+    pub(crate) cpu_affinity: Vec<usize>,
+    pub(crate) nice: Option<i32>,
+    pub(crate) sched: Option<SchedPolicy>,
+    pub(crate) report_on_interrupt: bool,
+}
+
+impl Default for Benchmark {
+    /// Same as the defaults of the command line options.
+    fn default() -> Self {
+        Self {
+            commands: Vec::new(),
+            runs: 10,
+            loops: 1,
+            backend: Backend::default(),
+            shell: String::from("sh"),
+            shell_arg: String::from("-c"),
+            builtin: String::from("time"),
+            bsd: String::from("/usr/bin/env time -l"),
+            gnu: String::from("gtime -v"),
+            busybox: String::from("busybox time -v"),
+            options: ExecOptions::default(),
+            metrics: Vec::new(),
+            sample_memory: false,
+            process_tree: false,
+            cgroup: false,
+            memory_limit: None,
+            cpu_quota: None,
+            perf_counters: false,
+            proc_io: false,
+            cold_cache: Vec::new(),
+            drop_caches: false,
+            cpu_affinity: Vec::new(),
+            nice: None,
+            sched: None,
+            report_on_interrupt: false,
+        }
+    }
+}
+
+impl Benchmark {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a command to benchmark.
+    pub fn command(mut self, command: impl Into<BenchmarkCommand>) -> Self {
+        self.commands.push(command.into());
+        self
+    }
+
+    /// Perform NUM runs for each command.
+    pub fn runs(mut self, runs: u16) -> Self {
+        self.runs = runs;
+        self
+    }
+
+    /// Loop NUM times with one measurement run for each command. See `--loops` for details.
+    pub fn loops(mut self, loops: u16) -> Self {
+        self.loops = loops;
+        self
+    }
+
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Set the shell to use for executing benchmarked commands.
+    pub fn shell(mut self, shell: impl Into<String>) -> Self {
+        self.shell = shell.into();
+        self
+    }
+
+    /// Set the shell args to use for executing benchmarked commands.
+    pub fn shell_arg(mut self, shell_arg: impl Into<String>) -> Self {
+        self.shell_arg = shell_arg.into();
+        self
+    }
+
+    /// Change shell built-in time command.
+    pub fn builtin(mut self, builtin: impl Into<String>) -> Self {
+        self.builtin = builtin.into();
+        self
+    }
+
+    /// Change BSD time command.
+    pub fn bsd(mut self, bsd: impl Into<String>) -> Self {
+        self.bsd = bsd.into();
+        self
+    }
+
+    /// Change GNU time command.
+    pub fn gnu(mut self, gnu: impl Into<String>) -> Self {
+        self.gnu = gnu.into();
+        self
+    }
+
+    /// Change BusyBox time command.
+    pub fn busybox(mut self, busybox: impl Into<String>) -> Self {
+        self.busybox = busybox.into();
+        self
+    }
+
+    /// Add an environment variable for all commands.
+    pub fn env(mut self, key: impl Into<String>, val: impl Into<String>) -> Self {
+        self.options.envs.push((key.into(), val.into()));
+        self
+    }
+
+    /// Set the working directory for all commands.
+    pub fn cwd(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.options.cwd = Some(dir.into());
+        self
+    }
+
+    /// Add a metric extracted from the output of each run.
+    pub fn metric(mut self, metric: Metric) -> Self {
+        self.metrics.push(metric);
+        self
+    }
+
+    /// Sample the memory usage from /proc while running. Only on Linux.
+    pub fn sample_memory(mut self, sample_memory: bool) -> Self {
+        self.sample_memory = sample_memory;
+        self
+    }
+
+    /// Account all descendant processes, including orphaned ones, with the breakdown by executable name.
+    ///
+    /// This makes this process the subreaper during each run. See `--process-tree` for details. Only on Linux.
+    pub fn process_tree(mut self, process_tree: bool) -> Self {
+        self.process_tree = process_tree;
+        self
+    }
+
+    /// Run each run in a transient cgroup v2 and read its items. Only on Linux.
+    ///
+    /// If the cgroup cannot be created, it runs without the cgroup unless any limit is set.
+    /// This process is moved into a leaf cgroup while running, and moved back after.
+    pub fn cgroup(mut self, cgroup: bool) -> Self {
+        self.cgroup = cgroup;
+        self
+    }
+
+    /// Limit the memory of the cgroup to BYTES by `memory.max`. This implies cgroup().
+    pub fn memory_limit(mut self, bytes: u64) -> Self {
+        self.memory_limit = Some(bytes);
+        self
+    }
+
+    /// Limit the CPU time of the cgroup to CPUS, such as 1.5, by `cpu.max`. This implies cgroup().
+    pub fn cpu_quota(mut self, cpus: f64) -> Self {
+        self.cpu_quota = Some(cpus);
+        self
+    }
+
+    /// Count instructions, cycles, branch misses, cache misses and task clock by perf_event_open(2). Only on Linux.
+    pub fn perf_counters(mut self, perf_counters: bool) -> Self {
+        self.perf_counters = perf_counters;
+        self
+    }
+
+    /// Read the I/O of the process from /proc/pid/io when it exits. Only on Linux.
+    ///
+    /// With process_tree(), the ones of the orphaned processes are added.
+    pub fn proc_io(mut self, proc_io: bool) -> Self {
+        self.proc_io = proc_io;
+        self
+    }
+
+    /// Evict the page cache of the file, or all files under the directory, before each run. Only on Linux.
+    pub fn cold_cache(mut self, path: impl Into<std::path::PathBuf>) -> Self {
+        self.cold_cache.push(path.into());
+        self
+    }
+
+    /// Drop the page cache of the whole system before each run. Only on Linux, and needs root.
+    pub fn drop_caches(mut self, drop_caches: bool) -> Self {
+        self.drop_caches = drop_caches;
+        self
+    }
+
+    /// Run the commands only on the CPUs, such as isolated ones. Only on Linux.
+    pub fn cpu_affinity(mut self, cpus: Vec<usize>) -> Self {
+        self.cpu_affinity = cpus;
+        self
+    }
+
+    /// Run the commands with the nice value. Only on Unix.
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.nice = Some(nice);
+        self
+    }
+
+    /// Run the commands with the scheduling policy. Only on Linux.
+    pub fn sched(mut self, sched: SchedPolicy) -> Self {
+        self.sched = Some(sched);
+        self
+    }
+
+    /// Report the runs so far when the user quits, instead of discarding them.
+    ///
+    /// The result is marked as interrupted, and the command in progress as partial.
+    pub fn report_on_interrupt(mut self, report_on_interrupt: bool) -> Self {
+        self.report_on_interrupt = report_on_interrupt;
+        self
+    }
+
+    /// Why prepare_backends() returned no backends, which is only when the built-in fallback also failed.
+    pub(crate) fn no_backend_message(&self) -> String {
+        let selected = match self.backend {
+            Backend::Auto | Backend::AllAlternating => String::from(
+                "None of the BSD, GNU, BusyBox time and native backends is available. ",
+            ),
+            Backend::Native => String::from("The native backend is not available. "),
+            Backend::Gnu => format!("The GNU time `{}` is not available. ", self.gnu),
+            Backend::Bsd => format!("The BSD time `{}` is not available. ", self.bsd),
+            Backend::Busybox => format!("The BusyBox time `{}` is not available. ", self.busybox),
+            Backend::Builtin => String::new(),
+        };
+        format!(
+            "{}The built-in `{}` could not be executed by `{} {}` nor bash. Please check `--shell` and `--builtin`.",
+            selected, self.builtin, self.shell, self.shell_arg
+        )
+    }
+
+    /// True if each run is in a cgroup.
+    pub fn uses_cgroup(&self) -> bool {
+        self.cgroup || self.memory_limit.is_some() || self.cpu_quota.is_some()
+    }
+
+    /// Create the cgroup for one run if used.
+    ///
+    /// If it cannot be created without limits, None is returned to run without it.
+    pub(crate) fn create_cgroup(&self) -> anyhow::Result<Option<crate::cgroup::Cgroup>> {
+        if !self.uses_cgroup() {
+            return Ok(None);
+        }
+        match crate::cgroup::Cgroup::create(self.memory_limit, self.cpu_quota) {
+            Ok(cgroup) => Ok(Some(cgroup)),
+            Err(_) if self.memory_limit.is_none() && self.cpu_quota.is_none() => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub fn commands(&self) -> &[BenchmarkCommand] {
+        &self.commands
+    }
+
+    /// Execution options of the index-th command merged with the ones for all commands.
+    pub fn exec_options(&self, index: usize) -> ExecOptions {
+        let own = &self.commands[index].options;
+        ExecOptions {
+            envs: self.options.envs.iter().chain(&own.envs).cloned().collect(),
+            cwd: own.cwd.clone().or_else(|| self.options.cwd.clone()),
+            capture_output: !self.metrics.is_empty(),
+            cgroup: None,
+            perf_counters: self.perf_counters,
+            cpu_affinity: self.cpu_affinity.clone(),
+            nice: self.nice,
+            sched: self.sched,
+        }
+    }
+
+    /// Measure all commands, blocking until finished.
+    pub fn run(&self) -> anyhow::Result<BenchmarkResult> {
+        self.run_with(&mut Vec::<Box<dyn Reporter>>::new())
+    }
+
+    /// Measure all commands while reporting the progress, blocking until finished.
+    pub fn run_with(&self, reporter: &mut dyn Reporter) -> anyhow::Result<BenchmarkResult> {
+        let mut wait = sleep_tick;
+        let mut backends = self.prepare_backends(&mut wait).unwrap_or_default();
+        anyhow::ensure!(!backends.is_empty(), self.no_backend_message());
+        Ok(self
+            .run_reporting(&mut backends, reporter, &mut no_action)?
+            .unwrap_or_default())
+    }
+
+    /// Measure all commands with the given backends instead of the ones selected by backend().
+    ///
+    /// The available ones among them are used alternately.
+    /// This is intended for custom backends, such as a mock for deterministic testing.
+    pub fn run_with_backends(
+        &self,
+        backends: Vec<Box<dyn MeasureBackend>>,
+        reporter: &mut dyn Reporter,
+    ) -> anyhow::Result<BenchmarkResult> {
+        let mut wait = sleep_tick;
+        let mut available = Vec::new();
+        for mut backend in backends {
+            if backend_available(&mut wait, backend.as_mut()) == Some(true) {
+                available.push(backend);
+            }
+        }
+        anyhow::ensure!(!available.is_empty(), "No available backend.");
+        Ok(self
+            .run_reporting(&mut available, reporter, &mut no_action)?
+            .unwrap_or_default())
+    }
+
+    /// Measure all commands with the prepared backends.
+    ///
+    /// `wait` is called every tick while measuring, and returns the action of the user.
+    /// Pause takes effect after the current run, and Skip finishes the current command
+    /// with the runs so far, marked as partial.
+    /// If it quits, None is returned, or with report_on_interrupt, the result so far marked as interrupted.
+    /// The command in progress is included as partial, even if it has no runs yet.
+    pub(crate) fn run_reporting(
+        &self,
+        backends: &mut [Box<dyn MeasureBackend>],
+        reporter: &mut dyn Reporter,
+        wait: &mut dyn FnMut() -> UserAction,
+    ) -> anyhow::Result<Option<BenchmarkResult>> {
+        // Kept over the runs, so that this process is not moved between the cgroups on each run.
+        let _cgroup_parent = if self.uses_cgroup() {
+            crate::cgroup::Parent::shared().ok()
+        } else {
+            None
+        };
+        let metadata = Metadata::capture(self, backends);
+        reporter.all_start(&metadata)?;
+        let mut result = BenchmarkResult {
+            metadata: Some(metadata),
+            ..Default::default()
+        };
+        // Paused is kept over the commands.
+        let mut state = RunState::default();
+        for (index, target) in self.commands.iter().enumerate() {
+            reporter.benchmark_start(index, target)?;
+            let mut command_result = self.command_result(index);
+            command_result.system_before = Some(SystemState::capture());
+            state.runs = self.runs;
+            state.stop = None;
+            let mut n = 0;
+            while n < state.runs && state.stop.is_none() {
+                if state.paused {
+                    state.apply(wait());
+                    continue;
+                }
+                if let Some(run) = self.measure(index, n, backends, &mut || state.apply(wait()))? {
+                    reporter.run_finished(index, n, &run.backend, &run.report)?;
+                    command_result.runs.push(run.report);
+                    command_result.backends.push(run.backend);
+                    command_result.memory_series.push(run.memory_series);
+                    n += 1;
+                }
+            }
+            match state.stop {
+                Some(UserAction::Quit) if !self.report_on_interrupt => return Ok(None),
+                Some(UserAction::Quit) => {
+                    result.interrupted = true;
+                    command_result.partial = true;
+                }
+                Some(_) => command_result.partial = true,
+                None => {}
+            }
+            command_result.system_after = Some(SystemState::capture());
+            reporter.command_finished(index, &command_result)?;
+            result.commands.push(command_result);
+            if result.interrupted {
+                break;
+            }
+        }
+        reporter.all_finished(&result)?;
+        Ok(Some(result))
+    }
+
+    /// Make the result of the index-th command without any runs.
+    pub(crate) fn command_result(&self, index: usize) -> CommandResult {
+        let target = &self.commands[index];
+        CommandResult {
+            name: target.display_name().to_string(),
+            command: target.command.clone(),
+            loops: self.loops,
+            ..Default::default()
+        }
+    }
+
+    /// Execute the n-th run of the index-th command and wait for it to finish.
+    ///
+    /// The backends are used alternately, and the name of the used one is returned with the report.
+    /// If sample_memory, the process tree is sampled every tick while running.
+    /// The page cache is evicted beforehand by cold_cache and drop_caches.
+    /// If proc_io, /proc/pid/io is read after it exits and before the backend reaps it.
+    /// If uses_cgroup, it runs in a transient cgroup, which is removed after the run.
+    /// `wait` is called every tick while waiting, and returns true to quit.
+    /// If it quits, the running command is killed and None is returned.
+    pub(crate) fn measure(
+        &self,
+        index: usize,
+        n: u16,
+        backends: &mut [Box<dyn MeasureBackend>],
+        wait: &mut dyn FnMut() -> bool,
+    ) -> anyhow::Result<Option<RunReport>> {
+        let target = &self.commands[index].command;
+        if !self.cold_cache.is_empty() {
+            crate::cache::evict(&self.cold_cache)?;
+        }
+        if self.drop_caches {
+            crate::cache::drop_caches()?;
+        }
+        let mut options = self.exec_options(index);
+        let cgroup = self.create_cgroup()?;
+        options.cgroup = cgroup.as_ref().map(|x| x.path().to_path_buf());
+        let len = backends.len();
+        let time_cmd = &mut backends[(n as usize) % len];
+        // Set before the command spawns any process, and restored after the orphans are reaped.
+        let _subreaper = self
+            .process_tree
+            .then(crate::proc_sampler::ChildSubreaper::set);
+        // The counters are closed even if they are not taken, such as when spawning fails.
+        let _perf = options.perf_counters.then(crate::perf::Scope::enter);
+        if self.loops <= 1 {
+            time_cmd.execute(target, &options)?;
+        } else {
+            time_cmd.execute(
+                format!(
+                    "sh -c 'for i in {} ;do {};done'",
+                    vec!["0"; self.loops as usize].join(" "),
+                    target
+                )
+                .as_str(),
+                &options,
+            )?;
+        }
+        let perf = time_cmd
+            .pid()
+            .filter(|_| options.perf_counters)
+            .and_then(crate::perf::take_counters);
+        let mut sampler = if self.process_tree {
+            time_cmd.pid().map(|pid| {
+                crate::proc_sampler::ProcSampler::for_process_tree(
+                    pid,
+                    options.cgroup.clone(),
+                    self.sample_memory,
+                )
+            })
+        } else {
+            time_cmd
+                .pid()
+                .filter(|_| self.sample_memory)
+                .map(crate::proc_sampler::ProcSampler::new)
+        };
+        let io_pid = time_cmd.pid().filter(|_| self.proc_io);
+        loop {
+            if let Some(sampler) = &mut sampler {
+                sampler.sample();
+            }
+            if wait() {
+                time_cmd.kill()?;
+                if let Some(sampler) = &mut sampler {
+                    sampler.reap_orphans();
+                }
+                return Ok(None);
+            }
+            // The exited process is read before the backend reaps it.
+            let io = match io_pid {
+                Some(pid) if !crate::proc_sampler::has_exited(pid) => continue,
+                Some(pid) => crate::proc_sampler::io_meas_items(pid),
+                None => HashMap::new(),
+            };
+            if time_cmd.is_finished() {
+                let mut report = time_cmd.get_report()?.clone();
+                report.extend(io);
+                if let Some(cgroup) = &cgroup {
+                    report.extend(cgroup.meas_items());
+                }
+                if let Some(sampler) = &mut sampler {
+                    let orphans_io = sampler.reap_orphans();
+                    if self.proc_io {
+                        for (item, val) in orphans_io {
+                            *report.entry(item).or_insert(0.0) += val;
+                        }
+                    }
+                    sampler.account_total_cpu(&report);
+                    report.extend(sampler.meas_items());
+                }
+                if let Some(perf) = &perf {
+                    report.extend(perf.meas_items());
+                }
+                let memory_series = sampler.map(|x| x.into_samples()).unwrap_or_default();
+                if options.capture_output {
+                    let output = time_cmd.get_output()?;
+                    for metric in &self.metrics {
+                        if let Some(val) = metric.extract(&output) {
+                            report.insert(MeasItem::Metric(metric.name.clone(), metric.unit), val);
+                        }
+                    }
+                }
+                return Ok(Some(RunReport {
+                    backend: time_cmd.name().to_string(),
+                    report,
+                    memory_series,
+                }));
+            }
+        }
+    }
+
+    /// Checks and returns the backends to be used.
+    ///
+    /// Only one backend is used except for AllAlternating.
+    /// If the specified one is not available, use built-in.
+    ///
+    /// `wait` is called every tick while checking, and returns true to quit.
+    /// If it quits, None is returned.
+    pub(crate) fn prepare_backends(
+        &self,
+        wait: &mut dyn FnMut() -> bool,
+    ) -> Option<Vec<Box<dyn MeasureBackend>>> {
+        let groups = match self.backend {
+            Backend::Auto | Backend::AllAlternating => vec![
+                crate::cmd::bsd_time_candidates(self),
+                crate::cmd::gnu_time_candidates(self),
+                crate::cmd::busybox_time_candidates(self),
+                native_candidates(self),
+            ],
+            Backend::Native => vec![native_candidates(self)],
+            Backend::Gnu => vec![crate::cmd::gnu_time_candidates(self)],
+            Backend::Bsd => vec![crate::cmd::bsd_time_candidates(self)],
+            Backend::Busybox => vec![crate::cmd::busybox_time_candidates(self)],
+            Backend::Builtin => vec![],
+        };
+        let mut backends = Vec::new();
+        for candidates in groups {
+            if let Some(backend) = first_available(wait, candidates)? {
+                backends.push(backend);
+                if self.backend != Backend::AllAlternating {
+                    break;
+                }
+            }
+        }
+        if backends.is_empty() {
+            let candidates = crate::cmd::builtin_time_candidates(self);
+            if let Some(backend) = first_available(wait, candidates)? {
+                backends.push(backend);
+            }
+        }
+        Some(backends)
+    }
+}
+
+#[cfg(unix)]
+fn native_candidates(benchmark: &Benchmark) -> Vec<BackendFactory<'_>> {
+    crate::rusage::native_candidates(benchmark)
+}
+
+#[cfg(not(unix))]
+fn native_candidates(_benchmark: &Benchmark) -> Vec<BackendFactory<'_>> {
+    Vec::new()
+}
+
+impl From<&str> for BenchmarkCommand {
+    fn from(command: &str) -> Self {
+        Self::new(command)
+    }
+}
+
+impl From<String> for BenchmarkCommand {
+    fn from(command: String) -> Self {
+        Self::new(command)
+    }
+}
+
+/// Wait for one tick without quitting.
+fn sleep_tick() -> bool {
+    std::thread::sleep(std::time::Duration::from_millis(10));
+    false
+}
+
+/// Wait for one tick without any action of the user.
+fn no_action() -> UserAction {
+    sleep_tick();
+    UserAction::None
+}
+
+/// Action of the user while measuring, such as by the keys in the terminal.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub(crate) enum UserAction {
+    None,
+    Quit,
+    /// Pause after the current run.
+    Pause,
+    Resume,
+    /// Stop the current command and go to the next one, keeping the runs so far.
+    Skip,
+    /// Add runs to the current command.
+    AddRuns(u16),
+}
+
+/// Progress of the current command changed by the actions of the user.
+#[derive(Debug, Default)]
+struct RunState {
+    /// Total runs including the added ones.
+    runs: u16,
+    paused: bool,
+    /// Quit or Skip, if stopped.
+    stop: Option<UserAction>,
+}
+
+impl RunState {
+    /// Apply the action, and return true to stop the running command.
+    fn apply(&mut self, action: UserAction) -> bool {
+        match action {
+            UserAction::None => {}
+            UserAction::Quit | UserAction::Skip => self.stop = Some(action),
+            UserAction::Pause => self.paused = true,
+            UserAction::Resume => self.paused = false,
+            UserAction::AddRuns(runs) => self.runs = self.runs.saturating_add(runs),
+        }
+        self.stop.is_some()
+    }
+}
+
+/// Create the candidates in order and return the first available one.
+///
+/// If `wait` quits, None is returned.
+fn first_available(
+    wait: &mut dyn FnMut() -> bool,
+    candidates: Vec<BackendFactory>,
+) -> Option<Option<Box<dyn MeasureBackend>>> {
+    for create in candidates {
+        if let Ok(mut backend) = create() {
+            if backend_available(wait, backend.as_mut())? {
+                return Some(Some(backend));
+            }
+        }
+    }
+    Some(None)
+}
+
+/// Check if the specified backend is available.
+///
+/// If `wait` quits, None is returned.
+fn backend_available(
+    wait: &mut dyn FnMut() -> bool,
+    backend: &mut dyn MeasureBackend,
+) -> Option<bool> {
+    loop {
+        match backend.ready_status() {
+            crate::cmd::ReadyStatus::Checking => {}
+            crate::cmd::ReadyStatus::Ready => {
+                return Some(true);
+            }
+            crate::cmd::ReadyStatus::Error => {
+                return Some(false);
+            }
+        }
+
+        if wait() {
+            return None;
+        }
+    }
+}
+
+/// Measured data of all commands.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    pub commands: Vec<CommandResult>,
+    /// Where and how it was measured.
+    pub metadata: Option<Metadata>,
+    /// True if the user quit before all commands finished. The remaining commands are not included.
+    pub interrupted: bool,
+}
+
+/// Measured data of one command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CommandResult {
+    /// The label, or the command line if not labeled.
+    pub name: String,
+    pub command: String,
+    /// Loops for one run. Time-related values in `runs` are the total of all loops.
+    pub loops: u16,
+    /// Reports of each run.
+    pub runs: Vec<HashMap<MeasItem, f64>>,
+    /// Name of the backend that measured each run, in the same order as `runs`.
+    pub backends: Vec<String>,
+    /// Memory usage sampled while each run, in the same order as `runs`. Empty unless sampled.
+    pub memory_series: Vec<Vec<MemorySample>>,
+    /// State of the system just before the first run.
+    pub system_before: Option<SystemState>,
+    /// State of the system just after the last run.
+    pub system_after: Option<SystemState>,
+    /// True if stopped before all runs, such as skipped by the user.
+    pub partial: bool,
+}
+
+/// Measured data of one run.
+pub(crate) struct RunReport {
+    pub backend: String,
+    pub report: HashMap<MeasItem, f64>,
+    pub memory_series: Vec<MemorySample>,
+}
+
+impl CommandResult {
+    /// Samples of the item from each run. Runs that could not measure it are skipped.
+    pub fn samples(&self, item: &MeasItem) -> Vec<f64> {
+        self.runs
+            .iter()
+            .filter_map(|x| x.get(item))
+            .copied()
+            .collect()
+    }
+
+    pub fn stats(&self, item: &MeasItem) -> crate::stats::Stats {
+        crate::stats::Stats::new(&self.samples(item))
+    }
+
+    /// Split into the results of each backend, in order of first use.
+    ///
+    /// Items of different backends have different semantics, so they should not be merged.
+    pub fn by_backend(&self) -> Vec<CommandResult> {
+        let mut results = Vec::<CommandResult>::new();
+        for (n, (report, backend)) in self.runs.iter().zip(&self.backends).enumerate() {
+            let result = match results.iter_mut().find(|x| &x.backends[0] == backend) {
+                Some(result) => result,
+                None => {
+                    results.push(CommandResult {
+                        runs: Vec::new(),
+                        backends: Vec::new(),
+                        memory_series: Vec::new(),
+                        ..self.clone()
+                    });
+                    results.last_mut().unwrap()
+                }
+            };
+            result.runs.push(report.clone());
+            result.backends.push(backend.clone());
+            if let Some(series) = self.memory_series.get(n) {
+                result.memory_series.push(series.clone());
+            }
+        }
+        results
+    }
+
+    /// Items measured in at least one run, in the order of MeasItem.
+    ///
+    /// Unknown and Metric ones follow in the order of their names.
+    pub fn items(&self) -> Vec<MeasItem> {
+        let mut items: Vec<_> = MeasItem::iter()
+            .filter(|x| self.runs.iter().any(|r| r.contains_key(x)))
+            .collect();
+        let mut unknowns: Vec<_> = self
+            .runs
+            .iter()
+            .flat_map(|r| r.keys())
+            .filter(|x| {
+                matches!(x, MeasItem::Unknown(_) | MeasItem::Metric(..)) && !items.contains(x)
+            })
+            .cloned()
+            .collect();
+        unknowns.sort_by_key(crate::cmd::meas_item_key);
+        unknowns.dedup();
+        items.append(&mut unknowns);
+        items
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn benchmark_exec_options() {
+        let benchmark = Benchmark::new()
+            .env("KEY1", "global")
+            .cwd("/tmp")
+            .command("cmd1")
+            .command(
+                BenchmarkCommand::new("cmd2")
+                    .name("second")
+                    .env("KEY1", "local")
+                    .cwd("/"),
+            );
+        assert_eq!(
+            benchmark.exec_options(0),
+            ExecOptions {
+                envs: vec![("KEY1".to_string(), "global".to_string())],
+                cwd: Some(std::path::PathBuf::from("/tmp")),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            benchmark.exec_options(1),
+            ExecOptions {
+                envs: vec![
+                    ("KEY1".to_string(), "global".to_string()),
+                    ("KEY1".to_string(), "local".to_string())
+                ],
+                cwd: Some(std::path::PathBuf::from("/")),
+                ..Default::default()
+            }
+        );
+        assert_eq!(benchmark.commands()[0].display_name(), "cmd1");
+        assert_eq!(benchmark.commands()[1].display_name(), "second");
+    }
+
+    #[test]
+    fn command_result_items() {
+        let result = CommandResult {
+            runs: vec![
+                HashMap::from([(MeasItem::Sys, 2.0), (MeasItem::Real, 1.0)]),
+                HashMap::from([
+                    (MeasItem::Real, 3.0),
+                    (MeasItem::Unknown("b".to_string()), 1.0),
+                    (MeasItem::Unknown("a".to_string()), 1.0),
+                ]),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            result.items(),
+            vec![
+                MeasItem::Real,
+                MeasItem::Sys,
+                MeasItem::Unknown("a".to_string()),
+                MeasItem::Unknown("b".to_string())
+            ]
+        );
+        assert_eq!(result.samples(&MeasItem::Real), vec![1.0, 3.0]);
+        assert_eq!(result.samples(&MeasItem::Sys), vec![2.0]);
+        assert_eq!(result.stats(&MeasItem::Real).mean, 2.0);
+    }
+
+    #[test]
+    fn command_result_by_backend() {
+        let result = CommandResult {
+            name: String::from("cmd"),
+            runs: vec![
+                HashMap::from([(MeasItem::Real, 1.0)]),
+                HashMap::from([(MeasItem::Real, 2.0)]),
+                HashMap::from([(MeasItem::Real, 3.0)]),
+            ],
+            backends: vec![
+                String::from("bsd"),
+                String::from("gnu"),
+                String::from("bsd"),
+            ],
+            ..Default::default()
+        };
+        let results = result.by_backend();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].name, "cmd");
+        assert_eq!(results[0].backends, vec!["bsd", "bsd"]);
+        assert_eq!(results[0].samples(&MeasItem::Real), vec![1.0, 3.0]);
+        assert_eq!(results[1].backends, vec!["gnu"]);
+        assert_eq!(results[1].samples(&MeasItem::Real), vec![2.0]);
+    }
+
+    /// Finishes each run at once with Real of the count of runs.
+    #[derive(Default)]
+    struct MockBackend {
+        count: f64,
+        report: HashMap<MeasItem, f64>,
+    }
+
+    impl MeasureBackend for MockBackend {
+        fn name(&self) -> &str {
+            "mock"
+        }
+        fn ready_status(&mut self) -> crate::cmd::ReadyStatus {
+            crate::cmd::ReadyStatus::Ready
+        }
+        fn execute(&mut self, _command: &str, _options: &ExecOptions) -> anyhow::Result<()> {
+            self.count += 1.0;
+            self.report = HashMap::from([(MeasItem::Real, self.count)]);
+            Ok(())
+        }
+        fn is_finished(&mut self) -> bool {
+            true
+        }
+        fn get_report(&mut self) -> anyhow::Result<&HashMap<MeasItem, f64>> {
+            Ok(&self.report)
+        }
+        fn kill(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn benchmark_user_actions() {
+        let benchmark = Benchmark::new().command("cmd1").command("cmd2").runs(2);
+        let mut backends: Vec<Box<dyn MeasureBackend>> = vec![Box::<MockBackend>::default()];
+        // One tick per run, and one per tick while paused.
+        let mut actions = std::collections::VecDeque::from([
+            UserAction::AddRuns(1),
+            UserAction::Pause,
+            UserAction::None,
+            UserAction::Resume,
+            UserAction::None,
+            UserAction::Skip,
+        ]);
+        let result = benchmark
+            .run_reporting(&mut backends, &mut Vec::new(), &mut || {
+                actions.pop_front().unwrap_or(UserAction::None)
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            result.commands[0].samples(&MeasItem::Real),
+            vec![1.0, 2.0, 3.0]
+        );
+        assert!(!result.commands[0].partial);
+        // Skipped in the first run, which is discarded.
+        assert!(result.commands[1].runs.is_empty());
+        assert!(result.commands[1].partial);
+
+        let result = benchmark
+            .run_reporting(&mut backends, &mut Vec::new(), &mut || UserAction::Quit)
+            .unwrap();
+        assert!(result.is_none());
+
+        // Quit in the second run of the first command.
+        let benchmark = benchmark.report_on_interrupt(true);
+        let mut actions = std::collections::VecDeque::from([UserAction::None, UserAction::Quit]);
+        let result = benchmark
+            .run_reporting(&mut backends, &mut Vec::new(), &mut || {
+                actions.pop_front().unwrap_or(UserAction::None)
+            })
+            .unwrap()
+            .unwrap();
+        assert!(result.interrupted);
+        assert_eq!(result.commands.len(), 1);
+        assert_eq!(result.commands[0].runs.len(), 1);
+        assert!(result.commands[0].partial);
+
+        // Quit before any runs, so the command is reported as partial without runs.
+        let result = benchmark
+            .run_reporting(&mut backends, &mut Vec::new(), &mut || UserAction::Quit)
+            .unwrap()
+            .unwrap();
+        assert!(result.interrupted);
+        assert_eq!(result.commands.len(), 1);
+        assert!(result.commands[0].runs.is_empty());
+        assert!(result.commands[0].partial);
+    }
+}
@@ -0,0 +1,580 @@
+// Copyright © ArkBig
+//! This file provides the full-screen browser of the results by `--tui`.
+//!
+//! It shows all commands and items in a table, which can be sorted to compare the commands,
+//! and drills into the samples, outliers and histogram of one item.
+//! It runs on the alternate screen, so the inline reports remain after quitting.
+
+use crossterm::ExecutableCommand as _;
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Paragraph};
+
+use crate::benchmark::BenchmarkResult;
+use crate::cmd::{meas_item_key, meas_item_name, meas_item_unit_value, MeasItem};
+use crate::stats::Stats;
+
+/// Browse the result until quit.
+pub fn run(result: &BenchmarkResult) -> anyhow::Result<()> {
+    // Not raw yet if stderr is redirected.
+    let raw = crossterm::terminal::is_raw_mode_enabled()?;
+    if !raw {
+        crossterm::terminal::enable_raw_mode()?;
+    }
+    std::io::stdout().execute(crossterm::terminal::EnterAlternateScreen)?;
+    let ret = run_on_screen(result);
+    std::io::stdout().execute(crossterm::terminal::LeaveAlternateScreen)?;
+    if !raw {
+        crossterm::terminal::disable_raw_mode()?;
+    }
+    ret
+}
+
+fn run_on_screen(result: &BenchmarkResult) -> anyhow::Result<()> {
+    let backend = ratatui::backend::CrosstermBackend::new(std::io::stdout());
+    let mut terminal = ratatui::Terminal::new(backend)?;
+    terminal.hide_cursor()?;
+    let mut browser = Browser::new(result);
+    loop {
+        terminal.draw(|f| browser.draw(f))?;
+        if let crossterm::event::Event::Key(key) = crossterm::event::read()? {
+            if key.kind == crossterm::event::KeyEventKind::Press && !browser.handle_key(key) {
+                break;
+            }
+        }
+    }
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+/// Order of the rows in the table.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum SortKey {
+    /// In order of the commands and the items, as measured.
+    Command,
+    /// Grouped by the item, and in ascending order of the mean in each group, to compare the commands.
+    Item,
+    Mean,
+    /// Coefficient of variation, to find unstable ones.
+    Cv,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Command => SortKey::Item,
+            SortKey::Item => SortKey::Mean,
+            SortKey::Mean => SortKey::Cv,
+            SortKey::Cv => SortKey::Command,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Command => "command",
+            SortKey::Item => "item",
+            SortKey::Mean => "mean",
+            SortKey::Cv => "CV",
+        }
+    }
+}
+
+/// One item of one command.
+struct Row {
+    /// 0-based index of the command.
+    index: usize,
+    /// The name of the command, with the backend if measured by multiple ones.
+    name: String,
+    item: MeasItem,
+    loops: u16,
+    /// Samples of each run in order, with the 1-based run number.
+    samples: Vec<(usize, f64)>,
+    stats: Stats,
+}
+
+impl Row {
+    fn unit_value(&self, val: f64) -> String {
+        meas_item_unit_value(&self.item, val, self.loops)
+    }
+}
+
+/// State of the browser.
+struct Browser {
+    rows: Vec<Row>,
+    /// Index of the rows in display order.
+    order: Vec<usize>,
+    sort: SortKey,
+    descending: bool,
+    /// Position in `order` of the selected row.
+    selected: usize,
+    /// Position in the samples of the selected row, if the detail is shown.
+    detail: Option<usize>,
+    table_state: ratatui::widgets::TableState,
+    list_state: ratatui::widgets::ListState,
+}
+
+impl Browser {
+    fn new(result: &BenchmarkResult) -> Self {
+        let mut rows = Vec::new();
+        for (index, command) in result.commands.iter().enumerate() {
+            let results = command.by_backend();
+            for result in &results {
+                let name = if results.len() <= 1 {
+                    result.name.clone()
+                } else {
+                    format!("{} [{}]", result.name, result.backends[0])
+                };
+                for item in result.items() {
+                    let samples: Vec<_> = result
+                        .runs
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(n, x)| Some((n + 1, *x.get(&item)?)))
+                        .collect();
+                    // Skip if can't measure, same as the inline reports.
+                    let required = matches!(
+                        item,
+                        MeasItem::Real | MeasItem::User | MeasItem::Sys | MeasItem::Metric(..)
+                    );
+                    if !required && !samples.iter().any(|x| x.1.to_bits() != 0) {
+                        continue;
+                    }
+                    let stats = Stats::new(&samples.iter().map(|x| x.1).collect::<Vec<_>>());
+                    rows.push(Row {
+                        index,
+                        name: name.clone(),
+                        item,
+                        loops: result.loops,
+                        samples,
+                        stats,
+                    });
+                }
+            }
+        }
+        let mut browser = Self {
+            order: (0..rows.len()).collect(),
+            rows,
+            sort: SortKey::Command,
+            descending: false,
+            selected: 0,
+            detail: None,
+            table_state: Default::default(),
+            list_state: Default::default(),
+        };
+        browser.sort_rows();
+        browser
+    }
+
+    fn sort_rows(&mut self) {
+        let rows = &self.rows;
+        let item_rank = |item: &MeasItem| {
+            (
+                <MeasItem as strum::IntoEnumIterator>::iter()
+                    .position(|x| &x == item)
+                    .unwrap_or(usize::MAX),
+                meas_item_key(item),
+            )
+        };
+        let selected = self.order.get(self.selected).copied();
+        self.order = (0..rows.len()).collect();
+        match self.sort {
+            // Stable, so they remain in order of the commands.
+            SortKey::Command => {}
+            SortKey::Item => self.order.sort_by(|&a, &b| {
+                item_rank(&rows[a].item)
+                    .cmp(&item_rank(&rows[b].item))
+                    .then(rows[a].stats.mean.total_cmp(&rows[b].stats.mean))
+            }),
+            SortKey::Mean => self
+                .order
+                .sort_by(|&a, &b| rows[a].stats.mean.total_cmp(&rows[b].stats.mean)),
+            SortKey::Cv => self
+                .order
+                .sort_by(|&a, &b| rows[a].stats.calc_cv().total_cmp(&rows[b].stats.calc_cv())),
+        }
+        if self.descending {
+            self.order.reverse();
+        }
+        // Keep the selected row.
+        self.selected = selected
+            .and_then(|x| self.order.iter().position(|&i| i == x))
+            .unwrap_or(0);
+    }
+
+    fn selected_row(&self) -> Option<&Row> {
+        self.rows.get(*self.order.get(self.selected)?)
+    }
+
+    /// Move the cursor by delta, within len.
+    fn move_cursor(cursor: usize, delta: isize, len: usize) -> usize {
+        cursor
+            .saturating_add_signed(delta)
+            .min(len.saturating_sub(1))
+    }
+
+    /// Handle the key, and return false to quit.
+    fn handle_key(&mut self, key: crossterm::event::KeyEvent) -> bool {
+        use crossterm::event::{KeyCode, KeyModifiers};
+        const PAGE: isize = 10;
+        let delta = match key.code {
+            KeyCode::Up | KeyCode::Char('k') => Some(-1),
+            KeyCode::Down | KeyCode::Char('j') => Some(1),
+            KeyCode::PageUp => Some(-PAGE),
+            KeyCode::PageDown => Some(PAGE),
+            KeyCode::Home | KeyCode::Char('g') => Some(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => Some(isize::MAX),
+            _ => None,
+        };
+        match (key.code, key.modifiers, self.detail) {
+            (KeyCode::Char('c'), KeyModifiers::CONTROL, _) | (KeyCode::Char('q'), _, _) => {
+                return false
+            }
+            (KeyCode::Esc | KeyCode::Backspace | KeyCode::Left, _, Some(_)) => self.detail = None,
+            (KeyCode::Esc, _, None) => return false,
+            (KeyCode::Enter | KeyCode::Right, _, None) => {
+                if self.selected_row().is_some() {
+                    self.detail = Some(0);
+                }
+            }
+            (KeyCode::Char('s'), _, None) => {
+                self.sort = self.sort.next();
+                self.sort_rows();
+            }
+            (KeyCode::Char('r'), _, None) => {
+                self.descending = !self.descending;
+                self.sort_rows();
+            }
+            (_, _, None) => {
+                if let Some(delta) = delta {
+                    self.selected = Self::move_cursor(self.selected, delta, self.order.len());
+                }
+            }
+            (_, _, Some(sample)) => {
+                if let Some(delta) = delta {
+                    let len = self.selected_row().map_or(0, |x| x.samples.len());
+                    self.detail = Some(Self::move_cursor(sample, delta, len));
+                }
+            }
+        }
+        true
+    }
+
+    fn draw(&mut self, f: &mut ratatui::Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(1)])
+            .split(f.size());
+        let help = if self.detail.is_some() {
+            String::from(" ↑↓: Sample  Esc: Back  q: Quit")
+        } else {
+            format!(
+                " ↑↓: Select  Enter: Detail  s: Sort by {}{}  r: Reverse  q: Quit",
+                self.sort.label(),
+                if self.descending { " (desc)" } else { "" }
+            )
+        };
+        f.render_widget(
+            Paragraph::new(help).style(Style::default().add_modifier(Modifier::REVERSED)),
+            chunks[1],
+        );
+        if self.detail.is_some() {
+            self.draw_detail(f, chunks[0]);
+        } else {
+            self.draw_table(f, chunks[0]);
+        }
+    }
+
+    fn draw_table(&mut self, f: &mut ratatui::Frame, area: ratatui::layout::Rect) {
+        use ratatui::widgets::{Cell, Row as TableRow, Table};
+        let header = TableRow::new(
+            [
+                "#", "Command", "Item", "Mean", "± σ", "CV", "Min", "Max", "Count", "Outlier",
+            ]
+            .map(Cell::from),
+        )
+        .style(
+            Style::default()
+                .fg(Color::Green)
+                .add_modifier(Modifier::BOLD),
+        );
+        let rows = self.order.iter().map(|&i| {
+            let row = &self.rows[i];
+            let stats = &row.stats;
+            TableRow::new(vec![
+                Cell::from((row.index + 1).to_string()),
+                Cell::from(row.name.clone()),
+                Cell::from(meas_item_name(&row.item, row.loops)),
+                Cell::from(row.unit_value(stats.mean)),
+                Cell::from(row.unit_value(stats.stdev)),
+                Cell::from(format!("{:.1} %", stats.calc_cv() * 100.0)),
+                Cell::from(row.unit_value(stats.min())),
+                Cell::from(row.unit_value(stats.max())),
+                Cell::from(stats.count().to_string()),
+                Cell::from(stats.outlier_count.to_string()).style(if stats.has_outlier() {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                }),
+            ])
+        });
+        let widths = [
+            Constraint::Length(3),
+            Constraint::Fill(1),
+            Constraint::Length(30),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(8),
+            Constraint::Length(11),
+            Constraint::Length(11),
+            Constraint::Length(5),
+            Constraint::Length(7),
+        ];
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(Block::default().borders(Borders::ALL).title(" mntime "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        self.table_state.select(Some(self.selected));
+        f.render_stateful_widget(table, area, &mut self.table_state);
+    }
+
+    fn draw_detail(&mut self, f: &mut ratatui::Frame, area: ratatui::layout::Rect) {
+        use ratatui::widgets::{Bar, BarChart, BarGroup, List, ListItem};
+        let Some(row) = self.selected_row() else {
+            return;
+        };
+        let stats = &row.stats;
+        let title = format!(
+            " #{} {} - {} ",
+            row.index + 1,
+            row.name,
+            meas_item_name(&row.item, row.loops).trim()
+        );
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(32), Constraint::Min(20)])
+            .split(area);
+        let right = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(9), Constraint::Min(5)])
+            .split(columns[1]);
+
+        let samples: Vec<_> = row
+            .samples
+            .iter()
+            .map(|&(n, x)| {
+                let item = ListItem::new(format!("{:>5}: {}", n, row.unit_value(x)));
+                if stats.is_outlier(x) {
+                    item.style(Style::default().fg(Color::Yellow))
+                } else {
+                    item
+                }
+            })
+            .collect();
+        let list = List::new(samples)
+            .block(Block::default().borders(Borders::ALL).title(" Runs "))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        let summary = [
+            (
+                "Mean",
+                format!(
+                    "{} ± {}",
+                    row.unit_value(stats.mean),
+                    row.unit_value(stats.stdev)
+                ),
+            ),
+            (
+                "Excluding outlier",
+                format!(
+                    "{} ± {}",
+                    row.unit_value(stats.mean_excluding_outlier),
+                    row.unit_value(stats.stdev_excluding_outlier)
+                ),
+            ),
+            (
+                "Min/Median/Max",
+                format!(
+                    "{} / {} / {}",
+                    row.unit_value(stats.min()),
+                    row.unit_value(stats.median()),
+                    row.unit_value(stats.max())
+                ),
+            ),
+            ("CV", format!("{:.1} %", stats.calc_cv() * 100.0)),
+            (
+                "LCL/UCL",
+                format!(
+                    "{} / {}",
+                    row.unit_value(stats.lcl),
+                    row.unit_value(stats.ucl)
+                ),
+            ),
+            (
+                "Outliers",
+                format!("{} of {} runs", stats.outlier_count, stats.count()),
+            ),
+        ]
+        .iter()
+        .map(|(label, value)| format!("{:>17}: {}", label, value))
+        .collect::<Vec<_>>()
+        .join("\n");
+        let summary =
+            Paragraph::new(summary).block(Block::default().borders(Borders::ALL).title(title));
+
+        // One bar is 1 column wide with 1 gap, inside the borders.
+        let bins = (usize::from(right[1].width.saturating_sub(2)) / 2).clamp(1, 20);
+        let width = (stats.max() - stats.min()) / bins as f64;
+        let bars: Vec<_> = stats
+            .histogram(bins)
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| {
+                let lower = stats.min() + width * i as f64;
+                let color = if stats.is_outlier(lower) || stats.is_outlier(lower + width) {
+                    Color::Yellow
+                } else {
+                    Color::Cyan
+                };
+                Bar::default()
+                    .value(count as u64)
+                    .text_value(String::new())
+                    .style(Style::default().fg(color))
+            })
+            .collect();
+        let histogram = BarChart::default()
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                " Histogram {} - {} ",
+                row.unit_value(stats.min()),
+                row.unit_value(stats.max())
+            )))
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(1)
+            .bar_gap(1);
+
+        self.list_state.select(self.detail);
+        f.render_stateful_widget(list, columns[0], &mut self.list_state);
+        f.render_widget(summary, right[0]);
+        f.render_widget(histogram, right[1]);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::benchmark::CommandResult;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use std::collections::HashMap;
+
+    fn sample_result() -> BenchmarkResult {
+        let command = |name: &str, reals: &[f64]| CommandResult {
+            name: String::from(name),
+            loops: 1,
+            runs: reals
+                .iter()
+                .map(|&x| HashMap::from([(MeasItem::Real, x), (MeasItem::MaxResident, 1024.0)]))
+                .collect(),
+            backends: vec![String::from("native"); reals.len()],
+            ..Default::default()
+        };
+        BenchmarkResult {
+            commands: vec![
+                command("slow", &[3.0, 3.0, 3.1, 3.0, 3.1, 3.0, 30.0]),
+                command("fast", &[1.0; 7]),
+            ],
+            ..Default::default()
+        }
+    }
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn names(browser: &Browser) -> Vec<(String, String)> {
+        browser
+            .order
+            .iter()
+            .map(|&i| {
+                let row = &browser.rows[i];
+                (row.name.clone(), meas_item_key(&row.item))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn browser_sort() {
+        let mut browser = Browser::new(&sample_result());
+        assert_eq!(browser.rows.len(), 2 * 2);
+        assert_eq!(
+            names(&browser)[0],
+            (String::from("slow"), String::from("Real"))
+        );
+
+        assert!(browser.handle_key(key(KeyCode::Char('s'))));
+        assert_eq!(browser.sort, SortKey::Item);
+        let real: Vec<_> = names(&browser)
+            .into_iter()
+            .filter(|x| x.1 == "Real")
+            .map(|x| x.0)
+            .collect();
+        assert_eq!(real, vec!["fast", "slow"]);
+        assert_eq!(
+            names(&browser)[0],
+            (String::from("fast"), String::from("Real"))
+        );
+
+        browser.handle_key(key(KeyCode::Char('s')));
+        browser.handle_key(key(KeyCode::Char('s')));
+        browser.handle_key(key(KeyCode::Char('r')));
+        assert_eq!(browser.sort, SortKey::Cv);
+        assert_eq!(
+            names(&browser)[0],
+            (String::from("slow"), String::from("Real"))
+        );
+    }
+
+    #[test]
+    fn browser_navigate() {
+        let mut browser = Browser::new(&sample_result());
+        browser.handle_key(key(KeyCode::Up));
+        assert_eq!(browser.selected, 0);
+        browser.handle_key(key(KeyCode::End));
+        assert_eq!(browser.selected, browser.rows.len() - 1);
+        browser.handle_key(key(KeyCode::Home));
+        browser.handle_key(key(KeyCode::Enter));
+        assert_eq!(browser.detail, Some(0));
+        browser.handle_key(key(KeyCode::PageDown));
+        assert_eq!(browser.detail, Some(6));
+        // Esc goes back, and quits on the table.
+        assert!(browser.handle_key(key(KeyCode::Esc)));
+        assert_eq!(browser.detail, None);
+        assert!(!browser.handle_key(key(KeyCode::Esc)));
+        assert!(!browser.handle_key(key(KeyCode::Char('q'))));
+    }
+
+    #[test]
+    fn browser_draw() {
+        let mut browser = Browser::new(&sample_result());
+        let mut terminal =
+            ratatui::Terminal::new(ratatui::backend::TestBackend::new(120, 20)).unwrap();
+        let text = |terminal: &ratatui::Terminal<ratatui::backend::TestBackend>| {
+            let buffer = terminal.backend().buffer();
+            buffer
+                .content()
+                .chunks(buffer.area.width as usize)
+                .map(|line| line.iter().map(|x| x.symbol()).collect::<String>())
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        terminal.draw(|f| browser.draw(f)).unwrap();
+        let table = text(&terminal);
+        assert!(table.contains("Elapsed (wall clock) time"));
+        assert!(table.contains("fast"));
+
+        browser.handle_key(key(KeyCode::Enter));
+        terminal.draw(|f| browser.draw(f)).unwrap();
+        let detail = text(&terminal);
+        assert!(detail.contains("#1 slow"));
+        assert!(detail.contains("Outliers: 1 of 7 runs"));
+        assert!(detail.contains("Histogram"));
+    }
+}
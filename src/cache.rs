@@ -0,0 +1,129 @@
+// Copyright © ArkBig
+//! This file provides eviction of the page cache before each run, to measure the cold start reproducibly.
+//!
+//! Files are evicted by [posix_fadvise(2)][fadvise] on Linux, which needs no privileges.
+//! Dropping all caches by [/proc/sys/vm/drop_caches][drop_caches] needs root.
+//!
+//! [fadvise]:https://man7.org/linux/man-pages/man2/posix_fadvise.2.html
+//! [drop_caches]:https://docs.kernel.org/admin-guide/sysctl/vm.html#drop-caches
+
+use anyhow::Context as _;
+
+#[derive(thiserror::Error, Debug)]
+enum CacheError {
+    #[cfg(not(target_os = "linux"))]
+    #[error("Evicting the page cache is available only on Linux.")]
+    Unsupported,
+    #[error("Could not evict the page cache of `{0}`: {1}")]
+    Evict(String, std::io::Error),
+}
+
+/// Evict the page cache of the files, and of all files under the directories.
+pub fn evict(paths: &[std::path::PathBuf]) -> anyhow::Result<()> {
+    for path in paths {
+        let error = |err| CacheError::Evict(path.display().to_string(), err);
+        let metadata = std::fs::metadata(path).map_err(error)?;
+        if metadata.is_dir() {
+            let entries: Vec<_> = std::fs::read_dir(path)
+                .map_err(error)?
+                .filter_map(|x| x.ok())
+                // Symbolic links are not followed, so that they do not loop.
+                .filter(|x| x.file_type().map(|t| !t.is_symlink()).unwrap_or(false))
+                .map(|x| x.path())
+                .collect();
+            evict(&entries)?;
+        } else if metadata.is_file() {
+            evict_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn evict_file(path: &std::path::Path) -> anyhow::Result<()> {
+    use std::os::fd::AsRawFd as _;
+    let error = |err| CacheError::Evict(path.display().to_string(), err);
+    let file = std::fs::File::open(path).map_err(error)?;
+    // Dirty pages are not evicted, so they are written back first.
+    let _ = file.sync_data();
+    // SAFETY: fd is valid while the file is open.
+    let err = unsafe { libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) };
+    if err != 0 {
+        anyhow::bail!(error(std::io::Error::from_raw_os_error(err)));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn evict_file(_path: &std::path::Path) -> anyhow::Result<()> {
+    anyhow::bail!(CacheError::Unsupported)
+}
+
+/// Write back and drop the page cache, dentries and inodes of the whole system. Only on Linux, and needs root.
+pub fn drop_caches() -> anyhow::Result<()> {
+    // SAFETY: sync has no preconditions.
+    #[cfg(unix)]
+    unsafe {
+        libc::sync();
+    }
+    std::fs::write("/proc/sys/vm/drop_caches", "3")
+        .context("Could not write to `/proc/sys/vm/drop_caches`.")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Number of pages of the file in the page cache, or None if it cannot be evicted, such as on tmpfs.
+    #[cfg(target_os = "linux")]
+    fn resident_pages(path: &std::path::Path) -> Option<usize> {
+        use std::os::fd::AsRawFd as _;
+        let file = std::fs::File::open(path).unwrap();
+        let len = file.metadata().unwrap().len() as usize;
+        // SAFETY: The buffers are valid during the calls, and the mapping is unmapped before the file is closed.
+        unsafe {
+            let mut stat: libc::statfs = std::mem::zeroed();
+            libc::fstatfs(file.as_raw_fd(), &mut stat);
+            if stat.f_type == libc::TMPFS_MAGIC {
+                return None;
+            }
+            let addr = libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            );
+            assert_ne!(addr, libc::MAP_FAILED);
+            let page = libc::sysconf(libc::_SC_PAGESIZE) as usize;
+            let mut vec = vec![0u8; len.div_ceil(page)];
+            let ret = libc::mincore(addr, len, vec.as_mut_ptr());
+            libc::munmap(addr, len);
+            assert_eq!(ret, 0);
+            Some(vec.iter().filter(|x| *x & 1 != 0).count())
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn cache_evict() {
+        let dir = std::env::temp_dir().join(format!("mntime-cache-evict-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        std::fs::write(dir.join("a"), "a").unwrap();
+        let b = dir.join("sub").join("b");
+        std::fs::write(&b, vec![b'b'; 64 * 1024]).unwrap();
+        std::fs::read(&b).unwrap();
+        let before = resident_pages(&b);
+        let result = evict(&[dir.clone(), dir.join("a")]);
+        let after = resident_pages(&b);
+        let missing = evict(&[dir.join("missing")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+        if let (Some(before), Some(after)) = (before, after) {
+            assert!(0 < before);
+            assert_eq!(after, 0);
+        }
+        assert!(missing.is_err());
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright © ArkBig
+//! This file provides the transient [cgroup v2][cgroup] to measure and limit the whole process tree of each run.
+//!
+//! The cgroup v2 does not allow a cgroup with processes to enable controllers for its children,
+//! so this process is moved into the leaf `mntime-<pid>/self` under the current cgroup,
+//! and the controllers are enabled in `mntime-<pid>`. The cgroup of each run is created next to the leaf
+//! and removed after it. When the last one is dropped, this process is moved back and both are removed.
+//!
+//! The controllers must be available in the current cgroup, such as a delegated one by systemd.
+//! Items are read only from the files of the available controllers.
+//!
+//! [cgroup]:https://docs.kernel.org/admin-guide/cgroup-v2.html
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context as _;
+
+use crate::cmd::MeasItem;
+
+#[derive(thiserror::Error, Debug)]
+enum CgroupError {
+    #[error("cgroup v2 is not mounted.")]
+    NotMounted,
+    #[error("The current cgroup v2 is unknown.")]
+    Unknown,
+    #[error("The {0} controller is not available in `{1}`. It must be enabled in `cgroup.subtree_control` of its parent, such as by a delegated cgroup.")]
+    NoController(&'static str, String),
+}
+
+/// Period of `cpu.max` in microseconds, same as the default of the kernel.
+const CPU_PERIOD: u64 = 100_000;
+
+/// Parent cgroup of the runs, whose leaf `self` has this process instead of the parent itself.
+///
+/// It is shared by the runs, and this process is moved back when the last one is dropped.
+pub(crate) struct Parent {
+    origin: PathBuf,
+    path: PathBuf,
+}
+
+impl Parent {
+    /// The existing one, or a new one under the current cgroup.
+    pub(crate) fn shared() -> anyhow::Result<std::sync::Arc<Self>> {
+        static SHARED: std::sync::Mutex<std::sync::Weak<Parent>> =
+            std::sync::Mutex::new(std::sync::Weak::new());
+
+        let mut shared = SHARED.lock().unwrap_or_else(|x| x.into_inner());
+        if let Some(parent) = shared.upgrade() {
+            return Ok(parent);
+        }
+        let parent = std::sync::Arc::new(Self::create()?);
+        *shared = std::sync::Arc::downgrade(&parent);
+        Ok(parent)
+    }
+
+    fn create() -> anyhow::Result<Self> {
+        let origin = current_dir()?;
+        let path = origin.join(format!("mntime-{}", std::process::id()));
+        std::fs::create_dir(&path)
+            .with_context(|| format!("Could not create the cgroup `{}`.", path.display()))?;
+        // Removed by drop if any of the following fails.
+        let parent = Self { origin, path };
+        let leaf = parent.leaf();
+        std::fs::create_dir(&leaf)
+            .with_context(|| format!("Could not create the cgroup `{}`.", leaf.display()))?;
+        std::fs::write(leaf.join("cgroup.procs"), std::process::id().to_string())
+            .with_context(|| format!("Could not move this process into `{}`.", leaf.display()))?;
+        // Some controllers may not be available, so each is tried separately.
+        for controller in ["+memory", "+cpu", "+io"] {
+            let _ = std::fs::write(parent.path.join("cgroup.subtree_control"), controller);
+        }
+        Ok(parent)
+    }
+
+    fn leaf(&self) -> PathBuf {
+        self.path.join("self")
+    }
+}
+
+impl Drop for Parent {
+    /// Move this process and its remaining children, such as of the version checks, back and remove the cgroups.
+    fn drop(&mut self) {
+        let leaf = self.leaf();
+        for _ in 0..100 {
+            let procs = std::fs::read_to_string(leaf.join("cgroup.procs")).unwrap_or_default();
+            for pid in procs.split_whitespace() {
+                let _ = std::fs::write(self.origin.join("cgroup.procs"), pid);
+            }
+            if std::fs::remove_dir(&leaf).is_ok() {
+                let _ = std::fs::remove_dir(&self.path);
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+}
+
+/// Transient cgroup of one run, which is removed when dropped.
+pub struct Cgroup {
+    path: PathBuf,
+    // Dropped after the cgroup is removed.
+    parent: std::sync::Arc<Parent>,
+}
+
+impl Cgroup {
+    /// Create the cgroup with the limits under the parent shared by the runs.
+    ///
+    /// `memory_limit` is in bytes and `cpu_quota` is in CPUs, such as 1.5.
+    pub fn create(memory_limit: Option<u64>, cpu_quota: Option<f64>) -> anyhow::Result<Self> {
+        static COUNT: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        let parent = Parent::shared()?;
+        let count = COUNT.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let path = parent.path.join(format!("run-{}", count));
+        std::fs::create_dir(&path)
+            .with_context(|| format!("Could not create the cgroup `{}`.", path.display()))?;
+        let cgroup = Self { path, parent };
+        if let Some(bytes) = memory_limit {
+            cgroup.write_limit("memory", "memory.max", &bytes.to_string())?;
+        }
+        if let Some(cpus) = cpu_quota {
+            let quota = (cpus * CPU_PERIOD as f64).round() as u64;
+            cgroup.write_limit("cpu", "cpu.max", &format!("{} {}", quota, CPU_PERIOD))?;
+        }
+        Ok(cgroup)
+    }
+
+    /// Directory of the cgroup, where the process is moved into by `ExecOptions::cgroup`.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Read the items of the finished run. Files of unavailable controllers are skipped.
+    pub fn meas_items(&self) -> HashMap<MeasItem, f64> {
+        let read = |name: &str| std::fs::read_to_string(self.path.join(name)).unwrap_or_default();
+        let mut items = HashMap::new();
+        if let Ok(peak) = read("memory.peak").trim().parse::<f64>() {
+            items.insert(MeasItem::CgroupMemoryPeak, peak);
+        }
+        items.extend(parse_memory_stat(&read("memory.stat")));
+        items.extend(parse_cpu_stat(&read("cpu.stat")));
+        items.extend(parse_io_stat(&read("io.stat")));
+        items
+    }
+
+    fn write_limit(&self, controller: &'static str, name: &str, val: &str) -> anyhow::Result<()> {
+        let file = self.path.join(name);
+        if !file.exists() {
+            anyhow::bail!(CgroupError::NoController(
+                controller,
+                self.parent.path.display().to_string()
+            ));
+        }
+        std::fs::write(&file, val)
+            .with_context(|| format!("Could not write `{}` to `{}`.", val, file.display()))
+    }
+}
+
+impl Drop for Cgroup {
+    /// Kill the remaining processes, such as daemons, and remove the cgroup.
+    fn drop(&mut self) {
+        for _ in 0..100 {
+            if std::fs::remove_dir(&self.path).is_ok() {
+                return;
+            }
+            let _ = std::fs::write(self.path.join("cgroup.kill"), "1");
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+    }
+}
+
+/// Directory of the cgroup of this process, from /proc/self/cgroup and /proc/self/mountinfo.
+fn current_dir() -> anyhow::Result<PathBuf> {
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").unwrap_or_default();
+    let mount = cgroup2_mount(&mountinfo).ok_or(CgroupError::NotMounted)?;
+    let cgroup = std::fs::read_to_string("/proc/self/cgroup").unwrap_or_default();
+    let current = cgroup
+        .lines()
+        .find_map(|x| x.strip_prefix("0::"))
+        .ok_or(CgroupError::Unknown)?;
+    Ok(Path::new(mount).join(current.trim_start_matches('/')))
+}
+
+/// Mount point of cgroup v2 from the content of /proc/self/mountinfo.
+fn cgroup2_mount(mountinfo: &str) -> Option<&str> {
+    mountinfo.lines().find_map(|line| {
+        let (mount, fs) = line.split_once(" - ")?;
+        if fs.split_whitespace().next()? != "cgroup2" {
+            return None;
+        }
+        mount.split_whitespace().nth(4)
+    })
+}
+
+/// Values of "key value" lines.
+fn flat_keyed(content: &str) -> HashMap<&str, f64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, val) = line.split_once(' ')?;
+            Some((key, val.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+fn parse_memory_stat(content: &str) -> HashMap<MeasItem, f64> {
+    let stat = flat_keyed(content);
+    [
+        ("file", MeasItem::CgroupPageCache),
+        ("pgfault", MeasItem::CgroupPageFault),
+        ("pgmajfault", MeasItem::CgroupMajorPageFault),
+    ]
+    .into_iter()
+    .filter_map(|(key, item)| Some((item, *stat.get(key)?)))
+    .collect()
+}
+
+fn parse_cpu_stat(content: &str) -> HashMap<MeasItem, f64> {
+    const USEC: f64 = 0.000_001;
+    let stat = flat_keyed(content);
+    [
+        ("usage_usec", MeasItem::CgroupCpuUsage, USEC),
+        ("user_usec", MeasItem::CgroupUser, USEC),
+        ("system_usec", MeasItem::CgroupSys, USEC),
+        ("nr_throttled", MeasItem::CgroupThrottled, 1.0),
+        ("throttled_usec", MeasItem::CgroupThrottledTime, USEC),
+    ]
+    .into_iter()
+    .filter_map(|(key, item, scale)| Some((item, *stat.get(key)? * scale)))
+    .collect()
+}
+
+/// Sum up all devices of "MAJ:MIN key=value ..." lines.
+fn parse_io_stat(content: &str) -> HashMap<MeasItem, f64> {
+    let mut items = HashMap::new();
+    for (key, val) in content
+        .lines()
+        .flat_map(|line| line.split_whitespace().skip(1))
+        .filter_map(|x| x.split_once('='))
+    {
+        let item = match key {
+            "rbytes" => MeasItem::CgroupReadBytes,
+            "wbytes" => MeasItem::CgroupWriteBytes,
+            "rios" => MeasItem::CgroupReadOps,
+            "wios" => MeasItem::CgroupWriteOps,
+            _ => continue,
+        };
+        if let Ok(val) = val.parse::<f64>() {
+            *items.entry(item).or_insert(0.0) += val;
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn cgroup_parse() {
+        let mountinfo = "22 28 0:21 / /proc rw,nosuid - proc proc rw\n\
+                         42 32 0:38 / /sys/fs/cgroup/unified rw,relatime - cgroup2 cgroup2 rw\n";
+        assert_eq!(cgroup2_mount(mountinfo), Some("/sys/fs/cgroup/unified"));
+        assert_eq!(
+            cgroup2_mount("22 28 0:21 / /proc rw - proc proc rw\n"),
+            None
+        );
+
+        let memory = parse_memory_stat("anon 0\nfile 40960\npgfault 1234\npgmajfault 5\n");
+        assert_eq!(memory[&MeasItem::CgroupPageCache], 40960.0);
+        assert_eq!(memory[&MeasItem::CgroupPageFault], 1234.0);
+        assert_eq!(memory[&MeasItem::CgroupMajorPageFault], 5.0);
+
+        let cpu = parse_cpu_stat(
+            "usage_usec 1500000\nuser_usec 1000000\nsystem_usec 500000\n\
+             nr_periods 30\nnr_throttled 20\nthrottled_usec 250000\n",
+        );
+        assert_eq!(cpu[&MeasItem::CgroupCpuUsage], 1.5);
+        assert_eq!(cpu[&MeasItem::CgroupUser], 1.0);
+        assert_eq!(cpu[&MeasItem::CgroupSys], 0.5);
+        assert_eq!(cpu[&MeasItem::CgroupThrottled], 20.0);
+        assert_eq!(cpu[&MeasItem::CgroupThrottledTime], 0.25);
+        // Without the cpu controller, only the usage is available.
+        assert_eq!(parse_cpu_stat("usage_usec 10\n").len(), 1);
+
+        let io = parse_io_stat(
+            "8:16 rbytes=1024 wbytes=2048 rios=1 wios=2 dbytes=0 dios=0\n\
+             8:0 rbytes=4096 wbytes=0 rios=3 wios=0 dbytes=0 dios=0\n",
+        );
+        assert_eq!(io[&MeasItem::CgroupReadBytes], 5120.0);
+        assert_eq!(io[&MeasItem::CgroupWriteBytes], 2048.0);
+        assert_eq!(io[&MeasItem::CgroupReadOps], 4.0);
+        assert_eq!(io[&MeasItem::CgroupWriteOps], 2.0);
+        assert!(parse_io_stat("").is_empty());
+    }
+}
@@ -8,7 +8,14 @@ pub fn parse() -> CliArgs {
 use clap::Parser as _;
 /// Command Line Arguments
 #[derive(Debug, clap::Parser)]
-#[clap(author, version, about, long_about = None, trailing_var_arg = true)]
+#[clap(
+    author,
+    version,
+    about,
+    long_about = None,
+    trailing_var_arg = true,
+    subcommand_negates_reqs = true
+)]
 pub struct CliArgs {
     /// Perform NUM runs for each command.
     #[clap(short, long, value_parser, value_name = "NUM", default_value_t = 10)]
@@ -46,7 +53,19 @@ pub struct CliArgs {
     #[clap(long, value_name = "ARG", default_value = "-c")]
     pub shell_arg: String,
 
+    /// Choose the backend to measure with.
+    ///
+    /// Only one backend is used except for `all-alternating`,
+    /// because items of different backends have different semantics.
+    /// If the chosen one is not available, use built-in.
+    ///
+    /// `all-alternating` runs the available ones alternately and reports them separately.
+    #[clap(long, value_enum, value_name = "BACKEND", default_value_t = crate::benchmark::Backend::Auto)]
+    pub backend: crate::benchmark::Backend,
+
     /// Use shell built-in time only.
+    ///
+    /// This is the same as `--backend builtin`.
     #[clap(long)]
     pub use_builtin_only: bool,
 
@@ -56,9 +75,9 @@ pub struct CliArgs {
 
     /// No BSD time.
     ///
-    /// The default is to try to run BSD and GNU alternately.
-    /// If neither of those is available, use built-in.
-    /// When this is specified, bsd time is not used.
+    /// This is only for compatibility and is ignored if `--backend` is specified.
+    /// When this is specified, bsd time is not used,
+    /// that is, the same as `--backend gnu`.
     #[clap(long)]
     pub no_bsd: bool,
 
@@ -68,9 +87,9 @@ pub struct CliArgs {
 
     /// No GNU time.
     ///
-    /// The default is to try to run BSD and GNU alternately.
-    /// If neither of those is available, use built-in.
-    /// When this is specified, gnu time is not used.
+    /// This is only for compatibility and is ignored if `--backend` is specified.
+    /// When this is specified, gnu time is not used,
+    /// that is, the same as `--backend bsd`.
     #[clap(long)]
     pub no_gnu: bool,
 
@@ -80,6 +99,233 @@ pub struct CliArgs {
     #[clap(long, value_name = "COMMAND", default_value = "gtime -v")]
     pub gnu: String,
 
+    /// Change BusyBox time command.
+    #[clap(long, value_name = "COMMAND", default_value = "busybox time -v")]
+    pub busybox: String,
+
+    /// Set an environment variable for all benchmarked commands.
+    ///
+    /// This is applied to the process executed as `sh -c time command1`,
+    /// so the time command and `env` itself are not included in the measurement.
+    ///
+    /// e.g.) --env RAYON_NUM_THREADS=4 --env MALLOC_ARENA_MAX=2
+    #[clap(long = "env", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    pub envs: Vec<(String, String)>,
+
+    /// Set an environment variable for the INDEX-th (1-based) command only.
+    ///
+    /// This takes precedence over `--env` with the same KEY.
+    ///
+    /// e.g.) --command-env 2:RAYON_NUM_THREADS=1
+    #[clap(long = "command-env", value_name = "INDEX:KEY=VALUE", value_parser = parse_command_env)]
+    pub command_envs: Vec<(usize, (String, String))>,
+
+    /// Set the working directory for all benchmarked commands.
+    #[clap(long, value_name = "DIR")]
+    pub cwd: Option<std::path::PathBuf>,
+
+    /// Set the working directory for the INDEX-th (1-based) command only.
+    ///
+    /// This takes precedence over `--cwd`.
+    ///
+    /// e.g.) --command-cwd 2:../other-checkout
+    #[clap(long = "command-cwd", value_name = "INDEX:DIR", value_parser = parse_command_cwd)]
+    pub command_cwds: Vec<(usize, std::path::PathBuf)>,
+
+    /// Export the statistics and samples of all commands to FILE as JSON.
+    #[clap(long, value_name = "FILE")]
+    pub export_json: Option<std::path::PathBuf>,
+
+    /// Export each sample to FILE as CSV as soon as the run finishes.
+    ///
+    /// The columns are `command_index,name,backend,run,item,value`.
+    /// A command stopped before all runs is followed by `# partial: command_index,name`.
+    #[clap(long, value_name = "FILE")]
+    pub export_csv: Option<std::path::PathBuf>,
+
+    /// Export a self-contained HTML report with the charts to FILE.
+    ///
+    /// Box plots, violin plots, each run and the means of the wall clock time and the maximum resident set size
+    /// are drawn in inline SVG for comparison, so the single file can be attached to release notes and issues.
+    #[clap(long, value_name = "FILE")]
+    pub export_html: Option<std::path::PathBuf>,
+
+    /// Export the samples and the gnuplot scripts to plot them to DIR.
+    ///
+    /// `ITEM.dat` and `ITEM.gp` are written for each item, such as `Real.dat` and `Real.gp`.
+    /// Run `gnuplot Real.gp` in DIR to render the box plots and each run to `Real.svg`,
+    /// or `gnuplot -e "format='pngcairo'" Real.gp` to `Real.png`.
+    #[clap(long, value_name = "DIR")]
+    pub export_gnuplot: Option<std::path::PathBuf>,
+
+    /// Export a Vega-Lite spec embedding the samples to FILE.
+    ///
+    /// The box plots and each run of the wall clock time and the maximum resident set size are charted.
+    /// It can be rendered offline, such as by `vl2svg` and `vl2png` of vega-lite.
+    #[clap(long, value_name = "FILE")]
+    pub export_vega: Option<std::path::PathBuf>,
+
+    /// Export the results of all commands to FILE as JSON in the same schema as `--export-json` of hyperfine.
+    ///
+    /// The times are per one loop. `user` and `system` are the means, or 0 if not measured.
+    #[clap(long, value_name = "FILE")]
+    pub export_hyperfine_json: Option<std::path::PathBuf>,
+
+    /// Print the histogram and box plot of the items after each report.
+    ///
+    /// ITEMS are comma-separated keys of the items, same as in `--export-json`, such as `Real,MaxResident`.
+    /// Without ITEMS, it is only the wall clock time.
+    /// The box plot spans the control limits of the outlier detection (LCL and UCL).
+    #[clap(
+        long,
+        value_name = "ITEMS",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "Real",
+        value_delimiter = ','
+    )]
+    pub histogram: Vec<String>,
+
+    /// Browse the results in a full-screen table after all commands are finished.
+    ///
+    /// The table can be sorted to compare the commands, and each item can be opened
+    /// to see the samples of each run, the outliers and the histogram.
+    /// It is ignored unless stdin and stdout are terminals.
+    #[clap(long)]
+    pub tui: bool,
+
+    /// Set a label for a command, shown in reports instead of the command line.
+    ///
+    /// Specify it multiple times to label the commands in order.
+    /// Commands without a label are shown as the command line.
+    ///
+    /// e.g.) mntime --name old --name new 'command1 --many --flags' 'command2 --many --flags'
+    #[clap(short, long = "name", value_name = "LABEL")]
+    pub names: Vec<String>,
+
+    /// Extract a numeric value from the stdout and stderr of each run as a custom item.
+    ///
+    /// The first capture group of REGEX is the value, or the whole match if there is no group.
+    /// If it matches more than once, such as with `--loops`, the mean is used.
+    ///
+    /// NAME must not be the key of a built-in item, such as `Real`.
+    /// UNIT is one of duration, bytes or count (default).
+    /// Duration is in seconds unless it has a suffix such as `ms`,
+    /// and bytes is in bytes unless it has a suffix such as `KiB`.
+    ///
+    /// e.g.) --metric 'parse:duration=parse: (\d+ms)' --metric 'rate=items/s: ([\d.]+)'
+    #[clap(long = "metric", value_name = "NAME[:UNIT]=REGEX", value_parser = parse_metric)]
+    pub metrics: Vec<crate::metric::Metric>,
+
+    /// Sample the memory usage from /proc every tick while running. Only on Linux.
+    ///
+    /// RSS, PSS, swap, threads and open files of the process tree are summed up,
+    /// including the shell and the time command.
+    /// Mean RSS and time to peak RSS are reported as well as the peaks,
+    /// and the time series is written by `--export-json`.
+    #[clap(long)]
+    pub sample_memory: bool,
+
+    /// Account all descendant processes with the breakdown by executable name. Only on Linux.
+    ///
+    /// Time commands report only the largest single process as "Maximum resident set size",
+    /// so the peak RSS of the combined process tree is reported as well.
+    /// The number of processes, CPU time and peak RSS are reported for each executable name,
+    /// such as "[cc] CPU time".
+    ///
+    /// mntime becomes the subreaper during each run, so that orphaned processes such as daemons are included.
+    /// Only the processes of the run are sampled and reaped, or the ones in its cgroup with `--cgroup`.
+    /// The processes are sampled from /proc every tick, and the CPU time of the ones exited between the samples
+    /// is reported as "[others] CPU time", from `cpu.stat` with `--cgroup` or else from the rusage.
+    #[clap(long)]
+    pub process_tree: bool,
+
+    /// Run each run in a transient cgroup v2 and report its items. Only on Linux.
+    ///
+    /// Peak memory, page cache and page faults by `memory.stat`,
+    /// CPU time and throttling by `cpu.stat` and I/O by `io.stat` of the whole process tree are reported.
+    /// Only the items of the controllers enabled in `cgroup.subtree_control` of the current cgroup are available.
+    /// If the cgroup cannot be created, such as without write permission, it runs without the cgroup.
+    #[clap(long)]
+    pub cgroup: bool,
+
+    /// Limit the memory of each run to SIZE by `memory.max` of the cgroup. This implies `--cgroup`.
+    ///
+    /// e.g.) --memory-limit 512MiB
+    #[clap(long, value_name = "SIZE", value_parser = parse_size)]
+    pub memory_limit: Option<u64>,
+
+    /// Limit the CPU time of each run to CPUS by `cpu.max` of the cgroup. This implies `--cgroup`.
+    ///
+    /// e.g.) --cpu-quota 1.5
+    #[clap(long, value_name = "CPUS", value_parser = parse_cpus)]
+    pub cpu_quota: Option<f64>,
+
+    /// Count hardware performance counters by perf_event_open(2). Only on Linux.
+    ///
+    /// Instructions, cycles, branch misses, cache misses and task clock of the whole process tree are counted,
+    /// in user space only. Instructions are much less noisy than the elapsed time.
+    /// Counters not permitted by `/proc/sys/kernel/perf_event_paranoid` or not supported, such as in VMs, are skipped.
+    #[clap(long)]
+    pub perf_counters: bool,
+
+    /// Read the I/O of each run from /proc/pid/io. Only on Linux.
+    ///
+    /// Bytes and syscalls of read and write, and bytes actually read from and written to storage, are reported.
+    /// Unlike block inputs and outputs of the time command, reads from the page cache are also counted.
+    /// The waited descendants are included, and with `--process-tree`, so are orphaned ones.
+    #[clap(long)]
+    pub proc_io: bool,
+
+    /// Evict the page cache of PATH before each run, to measure the cold start. Only on Linux.
+    ///
+    /// If PATH is a directory, all files under it are evicted. Specify it multiple times for more paths.
+    /// This needs no privileges, but dirty pages are written back first.
+    ///
+    /// e.g.) --cold-cache ./target/release/app --cold-cache ./assets
+    #[clap(long = "cold-cache", value_name = "PATH")]
+    pub cold_cache: Vec<std::path::PathBuf>,
+
+    /// Drop the page cache of the whole system before each run by `/proc/sys/vm/drop_caches`. Only on Linux.
+    ///
+    /// This needs root. If not permitted, it is ignored with a warning.
+    #[clap(long)]
+    pub drop_caches: bool,
+
+    /// Run the benchmarked commands only on the CPUs in LIST, such as isolated ones. Only on Linux.
+    ///
+    /// This is applied to the shell process, so unlike `taskset`, it is not a part of the measured command.
+    ///
+    /// e.g.) --cpu-affinity 2-3,6
+    #[clap(long, value_name = "LIST", value_parser = crate::sched::parse_cpu_list)]
+    pub cpu_affinity: Option<CpuList>,
+
+    /// Run the benchmarked commands with the nice value N. Only on Unix.
+    ///
+    /// Negative values need root or CAP_SYS_NICE.
+    #[clap(long, value_name = "N", allow_negative_numbers = true)]
+    pub nice: Option<i32>,
+
+    /// Run the benchmarked commands with the scheduling policy. Only on Linux.
+    #[clap(long, value_name = "POLICY", value_enum)]
+    pub sched: Option<crate::sched::SchedPolicy>,
+
+    /// Do not warn about the system state unsuitable for benchmarking.
+    ///
+    /// By default, it is warned before and after the runs of each command,
+    /// if the load average, the pressure (PSI) of CPU, memory or I/O, the CPU frequency governor
+    /// or turbo boost may disturb the measurement. The state is written by `--export-json` anyway.
+    #[clap(long)]
+    pub no_system_check: bool,
+
+    /// Report the runs so far when quit by `q` or Ctrl-C, instead of discarding them.
+    ///
+    /// The command in progress is reported with a warning that its results are partial,
+    /// and the exports are written marked as interrupted. The remaining commands are not run.
+    /// The exit code is 130 to tell it from a failure.
+    #[clap(long)]
+    pub report_on_interrupt: bool,
+
     /// The commands to benchmark.
     ///
     /// If multiple commands are specified, each is executed and compared.
@@ -88,8 +334,27 @@ pub struct CliArgs {
     /// the subsequent ones are considered to be the arguments of the command.
     ///
     /// e.g.) mntime command1 --flag arg -- command2 -- 'command3 -f -- args' command4 -o "output files"
+    ///
+    /// The first command named `import` is the subcommand, so specify it after `--`, such as `mntime -- import`.
     #[clap(value_parser, required = true)]
     commands: Vec<String>,
+
+    #[clap(subcommand)]
+    pub subcommand: Option<SubCommand>,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum SubCommand {
+    /// Report the results in FILE exported by `--export-json` of hyperfine, instead of benchmarking.
+    ///
+    /// The wall clock time and the exit status of each run are imported,
+    /// and reported in the same way as the benchmarked results, including the exports.
+    /// The options are specified before `import`, such as `mntime --export-html report.html import result.json`.
+    /// To benchmark a command named `import`, specify it after `--`.
+    Import {
+        #[clap(value_name = "FILE")]
+        file: std::path::PathBuf,
+    },
 }
 
 impl CliArgs {
@@ -124,6 +389,157 @@ impl CliArgs {
         }
         commands
     }
+
+    /// Convert to the benchmark settings.
+    ///
+    /// `--name`, `--command-env` and `--command-cwd` are matched to normalized_commands() in order.
+    pub fn to_benchmark(&self) -> crate::benchmark::Benchmark {
+        use crate::benchmark::{Backend, Benchmark, BenchmarkCommand};
+
+        let backend = if self.backend != Backend::Auto {
+            self.backend
+        } else if self.use_builtin_only || (self.no_bsd && self.no_gnu) {
+            Backend::Builtin
+        } else if self.no_bsd {
+            Backend::Gnu
+        } else if self.no_gnu {
+            Backend::Bsd
+        } else {
+            Backend::Auto
+        };
+        let mut benchmark = Benchmark::new()
+            .runs(self.runs)
+            .loops(self.loops)
+            .backend(backend)
+            .shell(&self.shell)
+            .shell_arg(&self.shell_arg)
+            .builtin(&self.builtin)
+            .bsd(&self.bsd)
+            .gnu(&self.gnu)
+            .busybox(&self.busybox);
+        benchmark = benchmark
+            .sample_memory(self.sample_memory)
+            .process_tree(self.process_tree)
+            .cgroup(self.cgroup)
+            .perf_counters(self.perf_counters)
+            .proc_io(self.proc_io)
+            .drop_caches(self.drop_caches)
+            .report_on_interrupt(self.report_on_interrupt);
+        if let Some(cpus) = &self.cpu_affinity {
+            benchmark = benchmark.cpu_affinity(cpus.clone());
+        }
+        if let Some(nice) = self.nice {
+            benchmark = benchmark.nice(nice);
+        }
+        if let Some(sched) = self.sched {
+            benchmark = benchmark.sched(sched);
+        }
+        for path in &self.cold_cache {
+            benchmark = benchmark.cold_cache(path);
+        }
+        if let Some(bytes) = self.memory_limit {
+            benchmark = benchmark.memory_limit(bytes);
+        }
+        if let Some(cpus) = self.cpu_quota {
+            benchmark = benchmark.cpu_quota(cpus);
+        }
+        for metric in &self.metrics {
+            benchmark = benchmark.metric(metric.clone());
+        }
+        for (key, val) in &self.envs {
+            benchmark = benchmark.env(key, val);
+        }
+        if let Some(cwd) = &self.cwd {
+            benchmark = benchmark.cwd(cwd);
+        }
+        for (index, command) in self.normalized_commands().into_iter().enumerate() {
+            let mut target = BenchmarkCommand::new(command);
+            if let Some(name) = self.names.get(index) {
+                target = target.name(name);
+            }
+            for (_, (key, val)) in self.command_envs.iter().filter(|x| x.0 == index + 1) {
+                target = target.env(key, val);
+            }
+            if let Some((_, cwd)) = self.command_cwds.iter().rev().find(|x| x.0 == index + 1) {
+                target = target.cwd(cwd);
+            }
+            benchmark = benchmark.command(target);
+        }
+        benchmark
+    }
+
+    /// The largest command index specified by `--command-env` and `--command-cwd`.
+    pub fn max_command_index(&self) -> usize {
+        self.command_envs
+            .iter()
+            .map(|x| x.0)
+            .chain(self.command_cwds.iter().map(|x| x.0))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Parsed as one value, because `Vec` would be parsed as multiple values by clap.
+type CpuList = Vec<usize>;
+
+fn parse_key_value(s: &str) -> Result<(String, String), String> {
+    match s.split_once('=') {
+        Some((key, val)) if !key.is_empty() => Ok((key.to_string(), val.to_string())),
+        _ => Err(format!("`{}` is not in KEY=VALUE format", s)),
+    }
+}
+
+fn parse_metric(s: &str) -> Result<crate::metric::Metric, String> {
+    use crate::metric::{Metric, MetricUnit};
+    let (name_unit, regex) = match s.split_once('=') {
+        Some((name_unit, regex)) if !name_unit.is_empty() && !regex.is_empty() => {
+            (name_unit, regex)
+        }
+        _ => return Err(format!("`{}` is not in NAME[:UNIT]=REGEX format", s)),
+    };
+    let (name, unit) = match name_unit.split_once(':') {
+        Some((name, unit)) => (
+            name,
+            <MetricUnit as clap::ValueEnum>::from_str(unit, true)
+                .map_err(|_| format!("`{}` is not one of duration, bytes or count", unit))?,
+        ),
+        None => (name_unit, MetricUnit::default()),
+    };
+    Metric::new(name, unit, regex).map_err(|err| err.to_string())
+}
+
+fn parse_size(s: &str) -> Result<u64, String> {
+    match crate::metric::parse_value(crate::metric::MetricUnit::Bytes, s) {
+        Some(bytes) if 0.0 < bytes => Ok(bytes as u64),
+        _ => Err(format!("`{}` is not a size such as 512MiB", s)),
+    }
+}
+
+fn parse_cpus(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(cpus) if 0.0 < cpus => Ok(cpus),
+        _ => Err(format!("`{}` is not a positive number of CPUs", s)),
+    }
+}
+
+fn parse_command_index(s: &str) -> Result<(usize, &str), String> {
+    let (index, rest) = s
+        .split_once(':')
+        .ok_or_else(|| format!("`{}` does not start with INDEX:", s))?;
+    match index.parse::<usize>() {
+        Ok(index) if 0 < index => Ok((index, rest)),
+        _ => Err(format!("`{}` is not a 1-based command index", index)),
+    }
+}
+
+fn parse_command_env(s: &str) -> Result<(usize, (String, String)), String> {
+    let (index, rest) = parse_command_index(s)?;
+    Ok((index, parse_key_value(rest)?))
+}
+
+fn parse_command_cwd(s: &str) -> Result<(usize, std::path::PathBuf), String> {
+    let (index, rest) = parse_command_index(s)?;
+    Ok((index, std::path::PathBuf::from(rest)))
 }
 
 fn is_quoted(str: &str) -> bool {
@@ -200,4 +616,168 @@ mod test {
             ]
         );
     }
+
+    #[test]
+    fn cli_args_to_benchmark_names() {
+        let names = |cli_args: CliArgs| -> Vec<String> {
+            cli_args
+                .to_benchmark()
+                .commands()
+                .iter()
+                .map(|x| x.display_name().to_string())
+                .collect()
+        };
+
+        // no labels
+        let cli_args = CliArgs::parse_from(vec!["mntime", "cmd1 arg1", "cmd2"]);
+        assert_eq!(names(cli_args), vec!["cmd1 arg1", "cmd2"]);
+
+        // labeled partially
+        let cli_args = CliArgs::parse_from(vec!["mntime", "--name", "first", "cmd1 arg1", "cmd2"]);
+        assert_eq!(names(cli_args), vec!["first", "cmd2"]);
+
+        // all labeled
+        let cli_args = CliArgs::parse_from(vec![
+            "mntime", "-n", "first", "-n", "second", "cmd1", "arg1", "--", "cmd2",
+        ]);
+        assert_eq!(names(cli_args), vec!["first", "second"]);
+    }
+
+    #[test]
+    fn cli_args_to_benchmark_backend() {
+        use crate::benchmark::Backend;
+        let backend = |args: &[&str]| -> Backend {
+            let args = [&["mntime"], args, &["cmd1"]].concat();
+            CliArgs::parse_from(args).to_benchmark().backend
+        };
+
+        assert_eq!(backend(&[]), Backend::Auto);
+        assert_eq!(backend(&["--backend", "native"]), Backend::Native);
+        assert_eq!(
+            backend(&["--backend", "all-alternating"]),
+            Backend::AllAlternating
+        );
+
+        // legacy options
+        assert_eq!(backend(&["--no-bsd"]), Backend::Gnu);
+        assert_eq!(backend(&["--no-gnu"]), Backend::Bsd);
+        assert_eq!(backend(&["--no-bsd", "--no-gnu"]), Backend::Builtin);
+        assert_eq!(backend(&["--use-builtin-only"]), Backend::Builtin);
+
+        // --backend takes precedence
+        assert_eq!(backend(&["--backend", "gnu", "--no-gnu"]), Backend::Gnu);
+    }
+
+    #[test]
+    fn cli_args_parse_metric() {
+        use crate::metric::MetricUnit;
+
+        let metric = parse_metric("parse:duration=parse: (\\d+ms)").unwrap();
+        assert_eq!(metric.name, "parse");
+        assert_eq!(metric.unit, MetricUnit::Duration);
+        assert_eq!(metric.regex.as_str(), "parse: (\\d+ms)");
+
+        let metric = parse_metric("rate=items/s: ([\\d.]+)").unwrap();
+        assert_eq!(metric.name, "rate");
+        assert_eq!(metric.unit, MetricUnit::Count);
+
+        assert!(parse_metric("rate").is_err());
+        assert!(parse_metric("=regex").is_err());
+        assert!(parse_metric("rate=").is_err());
+        assert!(parse_metric("rate:speed=regex").is_err());
+        assert!(parse_metric("rate=(").is_err());
+        assert!(parse_metric("Real=(\\d+)").is_err());
+    }
+
+    #[test]
+    fn cli_args_to_benchmark_cgroup() {
+        let cli_args = CliArgs::parse_from(vec!["mntime", "command1"]);
+        assert!(!cli_args.to_benchmark().uses_cgroup());
+
+        let cli_args = CliArgs::parse_from(vec![
+            "mntime",
+            "--memory-limit",
+            "512MiB",
+            "--cpu-quota",
+            "1.5",
+            "command1",
+        ]);
+        let benchmark = cli_args.to_benchmark();
+        assert!(benchmark.uses_cgroup());
+        assert_eq!(benchmark.memory_limit, Some(512 * 1024 * 1024));
+        assert_eq!(benchmark.cpu_quota, Some(1.5));
+
+        assert!(parse_size("1G").is_ok());
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("many").is_err());
+        assert!(parse_cpus("0").is_err());
+    }
+
+    #[test]
+    fn cli_args_to_benchmark_exec_options() {
+        let cli_args = CliArgs::parse_from(vec![
+            "mntime",
+            "--env",
+            "KEY1=global",
+            "--env",
+            "KEY2=a=b",
+            "--command-env",
+            "2:KEY1=local",
+            "--cwd",
+            "/tmp",
+            "--command-cwd",
+            "1:/",
+            "cmd1",
+            "--",
+            "cmd2",
+        ]);
+        let benchmark = cli_args.to_benchmark();
+        assert_eq!(benchmark.commands().len(), 2);
+        let options = benchmark.exec_options(0);
+        assert_eq!(
+            options.envs,
+            vec![
+                ("KEY1".to_string(), "global".to_string()),
+                ("KEY2".to_string(), "a=b".to_string())
+            ]
+        );
+        assert_eq!(options.cwd, Some(std::path::PathBuf::from("/")));
+        let options = benchmark.exec_options(1);
+        assert_eq!(
+            options.envs,
+            vec![
+                ("KEY1".to_string(), "global".to_string()),
+                ("KEY2".to_string(), "a=b".to_string()),
+                ("KEY1".to_string(), "local".to_string())
+            ]
+        );
+        assert_eq!(options.cwd, Some(std::path::PathBuf::from("/tmp")));
+        assert_eq!(cli_args.max_command_index(), 2);
+
+        // invalid formats
+        assert!(CliArgs::try_parse_from(vec!["mntime", "--env", "KEY", "cmd1"]).is_err());
+        assert!(CliArgs::try_parse_from(vec!["mntime", "--command-env", "K=V", "cmd1"]).is_err());
+        assert!(CliArgs::try_parse_from(vec!["mntime", "--command-cwd", "0:/", "cmd1"]).is_err());
+    }
+
+    #[test]
+    fn cli_args_import_subcommand() {
+        let cli_args = CliArgs::parse_from(vec!["mntime", "--runs=3", "import", "result.json"]);
+        assert!(matches!(
+            &cli_args.subcommand,
+            Some(SubCommand::Import { file }) if file == std::path::Path::new("result.json")
+        ));
+        assert!(cli_args.normalized_commands().is_empty());
+
+        // Not a command to benchmark, even without FILE.
+        assert!(CliArgs::try_parse_from(vec!["mntime", "import"]).is_err());
+
+        // Commands after `--`, or following the first command.
+        let cli_args = CliArgs::parse_from(vec!["mntime", "--", "import", "result.json"]);
+        assert!(cli_args.subcommand.is_none());
+        assert_eq!(cli_args.normalized_commands(), vec!["import 'result.json'"]);
+        let cli_args = CliArgs::parse_from(vec!["mntime", "cmd1", "--", "import"]);
+        assert!(cli_args.subcommand.is_none());
+        assert_eq!(cli_args.normalized_commands(), vec!["cmd1", "import"]);
+    }
 }
@@ -49,8 +49,43 @@ pub enum MeasItem {
     Page,
     Instruction,
     Cycle,
+    // Counted by `--perf-counters` on Linux.
+    BranchMiss,
+    CacheMiss,
+    TaskClock,
     PeakMemory,
+    // Sampled from /proc by `--sample-memory` on Linux.
+    MeanResident,
+    TimeToPeakResident,
+    PeakTreeResident,
+    PeakProportional,
+    PeakSwap,
+    PeakThreads,
+    PeakOpenFiles,
+    // Read from the transient cgroup v2 by `--cgroup` on Linux.
+    CgroupMemoryPeak,
+    CgroupPageCache,
+    CgroupPageFault,
+    CgroupMajorPageFault,
+    CgroupCpuUsage,
+    CgroupUser,
+    CgroupSys,
+    CgroupThrottled,
+    CgroupThrottledTime,
+    CgroupReadBytes,
+    CgroupWriteBytes,
+    CgroupReadOps,
+    CgroupWriteOps,
+    // Read from /proc/pid/io by `--proc-io` on Linux.
+    ReadChars,
+    WriteChars,
+    ReadSyscalls,
+    WriteSyscalls,
+    StorageRead,
+    StorageWrite,
     Unknown(String),
+    /// Custom item with its unit, such as the ones extracted from the output by `--metric`.
+    Metric(String, crate::metric::MetricUnit),
 }
 
 pub fn meas_item_name(item: &MeasItem, loops: u16) -> String {
@@ -84,25 +119,83 @@ pub fn meas_item_name(item: &MeasItem, loops: u16) -> String {
         MeasItem::Page => "Page size".to_string(),
         MeasItem::Instruction => "Instructions retired".to_string(),
         MeasItem::Cycle => "Cycles elapsed".to_string(),
+        MeasItem::BranchMiss => "Branch misses".to_string(),
+        MeasItem::CacheMiss => "Cache misses".to_string(),
+        MeasItem::TaskClock => format!("Task clock{}", loops_str),
         MeasItem::PeakMemory => "Peak memory footprint".to_string(),
-        MeasItem::Unknown(name) => String::from(name),
+        MeasItem::MeanResident => "Mean resident set size".to_string(),
+        MeasItem::TimeToPeakResident => "Time to peak resident set size".to_string(),
+        MeasItem::PeakTreeResident => "Peak RSS of the process tree".to_string(),
+        MeasItem::PeakProportional => "Peak proportional set size".to_string(),
+        MeasItem::PeakSwap => "Peak swap usage".to_string(),
+        MeasItem::PeakThreads => "Peak threads".to_string(),
+        MeasItem::PeakOpenFiles => "Peak open files".to_string(),
+        MeasItem::CgroupMemoryPeak => "Cgroup peak memory usage".to_string(),
+        MeasItem::CgroupPageCache => "Cgroup page cache".to_string(),
+        MeasItem::CgroupPageFault => format!("Cgroup page faults{}", loops_str),
+        MeasItem::CgroupMajorPageFault => format!("Cgroup major page faults{}", loops_str),
+        MeasItem::CgroupCpuUsage => format!("Cgroup CPU time{}", loops_str),
+        MeasItem::CgroupUser => format!("Cgroup user time{}", loops_str),
+        MeasItem::CgroupSys => format!("Cgroup system time{}", loops_str),
+        MeasItem::CgroupThrottled => format!("Cgroup throttled periods{}", loops_str),
+        MeasItem::CgroupThrottledTime => format!("Cgroup throttled time{}", loops_str),
+        MeasItem::CgroupReadBytes => "Cgroup I/O read bytes".to_string(),
+        MeasItem::CgroupWriteBytes => "Cgroup I/O written bytes".to_string(),
+        MeasItem::CgroupReadOps => format!("Cgroup I/O reads{}", loops_str),
+        MeasItem::CgroupWriteOps => format!("Cgroup I/O writes{}", loops_str),
+        MeasItem::ReadChars => "Bytes read by syscalls".to_string(),
+        MeasItem::WriteChars => "Bytes written by syscalls".to_string(),
+        MeasItem::ReadSyscalls => format!("Read syscalls{}", loops_str),
+        MeasItem::WriteSyscalls => format!("Write syscalls{}", loops_str),
+        MeasItem::StorageRead => "Bytes read from storage".to_string(),
+        MeasItem::StorageWrite => "Bytes written to storage".to_string(),
+        MeasItem::Unknown(name) | MeasItem::Metric(name, _) => String::from(name),
     }
 }
 
-pub fn meas_item_name_max_width(loops: u16) -> usize {
-    static WIDTH: once_cell::sync::OnceCell<usize> = once_cell::sync::OnceCell::new();
-    *WIDTH.get_or_init(|| {
-        let mut width = 0;
-        for item in MeasItem::iter() {
-            width = std::cmp::max(width, meas_item_name(&item, loops).len());
-        }
-        width
-    })
+/// Identifier of the item for machine-readable output.
+///
+/// This is the variant name, or the name as is for Unknown and Metric.
+pub fn meas_item_key(item: &MeasItem) -> String {
+    match item {
+        MeasItem::Unknown(name) | MeasItem::Metric(name, _) => name.clone(),
+        _ => item.as_ref().to_string(),
+    }
+}
+
+/// Width to align the names, which is the widest of the built-in items and the given ones,
+/// such as the metrics produced by the run.
+pub fn meas_item_name_max_width<'a>(
+    items: impl IntoIterator<Item = &'a MeasItem>,
+    loops: u16,
+) -> usize {
+    MeasItem::iter()
+        .chain(items.into_iter().cloned())
+        .map(|item| meas_item_name(&item, loops).chars().count())
+        .max()
+        .unwrap_or(0)
 }
 
 pub fn meas_item_unit_value(item: &MeasItem, val: f64, loops: u16) -> String {
     match item {
-        MeasItem::Real | MeasItem::User | MeasItem::Sys => {
+        // The value is already per one run, so it is not divided by loops.
+        MeasItem::Metric(_, unit) => match unit {
+            crate::metric::MetricUnit::Duration => meas_item_unit_value(&MeasItem::Real, val, 1),
+            crate::metric::MetricUnit::Bytes => {
+                meas_item_unit_value(&MeasItem::MaxResident, val, 1)
+            }
+            crate::metric::MetricUnit::Count => {
+                meas_item_unit_value(&MeasItem::Unknown(String::new()), val, 1)
+            }
+        },
+        MeasItem::Real
+        | MeasItem::User
+        | MeasItem::Sys
+        | MeasItem::TaskClock
+        | MeasItem::CgroupCpuUsage
+        | MeasItem::CgroupUser
+        | MeasItem::CgroupSys
+        | MeasItem::CgroupThrottledTime => {
             let v = if loops <= 1 { val } else { val / loops as f64 };
             const SIG_DIGS: i32 = 3;
             if v < 0.001 {
@@ -132,6 +225,8 @@ pub fn meas_item_unit_value(item: &MeasItem, val: f64, loops: u16) -> String {
                 )
             }
         }
+        // The time in one run including all loops.
+        MeasItem::TimeToPeakResident => meas_item_unit_value(&MeasItem::Real, val, 1),
         MeasItem::CpuUsage => {
             format!("{} %", round_precision(val, 2))
         }
@@ -141,7 +236,19 @@ pub fn meas_item_unit_value(item: &MeasItem, val: f64, loops: u16) -> String {
         | MeasItem::AvgStack
         | MeasItem::AvgTotal
         | MeasItem::AvgResident
-        | MeasItem::PeakMemory => {
+        | MeasItem::PeakMemory
+        | MeasItem::MeanResident
+        | MeasItem::PeakTreeResident
+        | MeasItem::PeakProportional
+        | MeasItem::PeakSwap
+        | MeasItem::CgroupMemoryPeak
+        | MeasItem::CgroupPageCache
+        | MeasItem::CgroupReadBytes
+        | MeasItem::CgroupWriteBytes
+        | MeasItem::ReadChars
+        | MeasItem::WriteChars
+        | MeasItem::StorageRead
+        | MeasItem::StorageWrite => {
             const SIG_DIGS: i32 = 3;
             const KB: f64 = 1024.0;
             const MB: f64 = 1024.0 * KB;
@@ -178,7 +285,14 @@ pub fn meas_item_unit_value(item: &MeasItem, val: f64, loops: u16) -> String {
         | MeasItem::BlockOutput
         | MeasItem::MsgSend
         | MeasItem::MsgRecv
-        | MeasItem::SignalRecv => {
+        | MeasItem::SignalRecv
+        | MeasItem::CgroupPageFault
+        | MeasItem::CgroupMajorPageFault
+        | MeasItem::CgroupThrottled
+        | MeasItem::CgroupReadOps
+        | MeasItem::CgroupWriteOps
+        | MeasItem::ReadSyscalls
+        | MeasItem::WriteSyscalls => {
             let v = if loops <= 1 { val } else { val / loops as f64 };
             const SIG_DIGS: i32 = 3;
             let precision = if v < 1.0 {
@@ -198,7 +312,11 @@ pub fn meas_item_unit_value(item: &MeasItem, val: f64, loops: u16) -> String {
         MeasItem::ExitStatus
         | MeasItem::Instruction
         | MeasItem::Cycle
+        | MeasItem::BranchMiss
+        | MeasItem::CacheMiss
         | MeasItem::Page
+        | MeasItem::PeakThreads
+        | MeasItem::PeakOpenFiles
         | MeasItem::Unknown(_) => {
             const SIG_DIGS: i32 = 3;
             let precision = if val < 1.0 {
@@ -227,59 +345,184 @@ enum CmdError {
     ParseError(&'static str),
 }
 
-#[derive(Eq, PartialEq)]
-pub enum CmdType {
-    Builtin,
-    Bsd,
-    Gnu,
+/// Options applied to the process of the benchmarked command.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExecOptions {
+    /// Environment variables to add. If the same key appears more than once, the later one wins.
+    pub envs: Vec<(String, String)>,
+    /// Working directory. The current one is inherited if None.
+    pub cwd: Option<std::path::PathBuf>,
+    /// Keep stdout and stderr of the command for get_output(), instead of discarding them.
+    pub capture_output: bool,
+    /// cgroup v2 directory to move the process into before it starts. Only on Linux.
+    pub cgroup: Option<std::path::PathBuf>,
+    /// Open the hardware performance counters for the process before it starts. Only on Linux.
+    ///
+    /// They are taken by `perf::take_counters()` with the pid.
+    pub perf_counters: bool,
+    /// CPUs to run the process on. All CPUs if empty. Only on Linux.
+    pub cpu_affinity: Vec<usize>,
+    /// Nice value of the process. Only on Unix.
+    pub nice: Option<i32>,
+    /// Scheduling policy of the process. Only on Linux.
+    pub sched: Option<crate::sched::SchedPolicy>,
+}
+
+/// Measurement method of the benchmarked command.
+///
+/// Each run is executed by execute(), polled by is_finished() every tick,
+/// and then get_report() returns the measured items.
+pub trait MeasureBackend {
+    /// Short name to identify the backend, such as "gnu".
+    fn name(&self) -> &str;
+
+    /// Check if the backend is available.
+    ///
+    /// This is polled every tick until it is not Checking.
+    /// execute() is called only after it becomes Ready.
+    fn ready_status(&mut self) -> ReadyStatus;
+
+    /// Start measurement.
+    fn execute(&mut self, command: &str, options: &ExecOptions) -> anyhow::Result<()>;
+
+    /// Check if measurement has been finished.
+    fn is_finished(&mut self) -> bool;
+
+    /// Get the measured items after finished.
+    ///
+    /// ExitStatus should be included, as it is used to count failures.
+    fn get_report(&mut self) -> anyhow::Result<&HashMap<MeasItem, f64>>;
+
+    /// Stop measurement on the way.
+    fn kill(&mut self) -> anyhow::Result<()>;
+
+    /// Get stdout and stderr of the command after finished.
+    ///
+    /// This is used only if capture_output of ExecOptions is specified.
+    /// It may include the output of the backend itself, such as the time command.
+    fn get_output(&mut self) -> anyhow::Result<String> {
+        Ok(String::new())
+    }
+
+    /// Process ID of the running command, whose process tree is sampled while running.
+    fn pid(&self) -> Option<u32> {
+        None
+    }
+
+    /// Version string of the backend, such as the first line of `--version`. None if unknown.
+    fn version(&self) -> Option<String> {
+        None
+    }
 }
 
+/// Creates a backend and starts checking its availability.
+pub type BackendFactory<'a> = Box<dyn Fn() -> anyhow::Result<Box<dyn MeasureBackend>> + 'a>;
+
+/// Parses the stderr of the time command. Empty if it could not be parsed.
+type ParseMeasItems = fn(&str) -> HashMap<MeasItem, f64>;
+
 /// Processing of the time command is bundled.
 pub struct TimeCmd {
     sh: String,
     sh_arg: String,
-    pub cmd_type: CmdType,
+    name: &'static str,
     command: String,
     process: std::process::Child,
     ready_status: ReadyStatus,
-    parse_meas_items: fn(&str) -> HashMap<MeasItem, f64>,
+    parse_meas_items: ParseMeasItems,
     meas_report: Option<HashMap<MeasItem, f64>>,
+    stdout: Option<std::thread::JoinHandle<String>>,
+    output: Option<String>,
+}
+
+/// Formats of the shell built-in time.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum BuiltinFormat {
+    /// The default format of POSIX shells, such as `real 0m1.000s`.
+    Plain,
+    /// bash with TIMEFORMAT set to measure CPU usage in milliseconds.
+    Bash,
+    /// zsh with TIMEFMT set to measure memory and page faults as well.
+    Zsh,
+    /// The fixed format of fish.
+    Fish,
+}
+
+/// Candidates of the shell built-in time in order of fallback.
+///
+/// The richer formats are tried first, because the plain one also matches the outputs of the others.
+pub fn builtin_time_candidates(benchmark: &crate::benchmark::Benchmark) -> Vec<BackendFactory<'_>> {
+    [
+        (false, BuiltinFormat::Zsh),
+        (false, BuiltinFormat::Bash),
+        (false, BuiltinFormat::Fish),
+        (false, BuiltinFormat::Plain),
+        (true, BuiltinFormat::Bash),
+        (true, BuiltinFormat::Plain),
+    ]
+    .into_iter()
+    .map(|(fallback_sh, format)| -> BackendFactory {
+        Box::new(move || {
+            Ok(Box::new(try_new_builtin_time(
+                benchmark,
+                fallback_sh,
+                format,
+            )?))
+        })
+    })
+    .collect()
 }
 
 pub fn try_new_builtin_time(
-    cli_args: &crate::cli_args::CliArgs,
+    benchmark: &crate::benchmark::Benchmark,
     fallback_sh: bool,
+    format: BuiltinFormat,
 ) -> anyhow::Result<TimeCmd> {
+    // Labels are the names of struct rusage members, which are parsed by parse_timefmt_meas_items.
+    let (prefix, parse_meas_items): (&str, ParseMeasItems) = match format {
+        BuiltinFormat::Plain => ("", parse_builtin_meas_items),
+        BuiltinFormat::Bash => (
+            r"TIMEFORMAT=$'real %3R\nuser %3U\nsys %3S\ncpu %P';",
+            parse_timefmt_meas_items,
+        ),
+        BuiltinFormat::Zsh => (
+            r"TIMEFMT=$'real %mE\nuser %mU\nsys %mS\ncpu %P\nmaxrss %M\navgtext %X\navgdata %D\navgtotal %K\nmajflt %F\nminflt %R\nnswap %W\ninblock %I\noublock %O\nmsgsnd %s\nmsgrcv %r\nnsignals %k\nnvcsw %w\nnivcsw %c';",
+            parse_timefmt_meas_items,
+        ),
+        BuiltinFormat::Fish => ("", parse_fish_meas_items),
+    };
     TimeCmd::try_new_with_command(
         &if fallback_sh {
             "bash".to_string()
         } else {
-            cli_args.shell.clone()
-        },
-        &cli_args.shell_arg,
-        CmdType::Builtin,
-        &cli_args.builtin,
-        |err_msg| {
-            let mut meas_items = HashMap::<MeasItem, f64>::new();
-            let re = builtin_re();
-            for cap in re.captures_iter(err_msg) {
-                let (name, v) = capture_name_and_val(&cap);
-                match name {
-                    "real" => meas_items.insert(MeasItem::Real, v),
-                    "user" => meas_items.insert(MeasItem::User, v),
-                    "sys" => meas_items.insert(MeasItem::Sys, v),
-                    _ => meas_items.insert(MeasItem::Unknown(String::from(name)), v),
-                };
-            }
-            const MINIMUM_ITEM_COUNT: usize = 3;
-            if meas_items.len() < MINIMUM_ITEM_COUNT {
-                meas_items.clear();
-            }
-            meas_items
+            benchmark.shell.clone()
         },
+        &benchmark.shell_arg,
+        "builtin",
+        &format!("{}{}", prefix, benchmark.builtin),
+        parse_meas_items,
     )
 }
 
+fn parse_builtin_meas_items(err_msg: &str) -> HashMap<MeasItem, f64> {
+    let mut meas_items = HashMap::<MeasItem, f64>::new();
+    let re = builtin_re();
+    for cap in re.captures_iter(err_msg) {
+        let (name, v) = capture_name_and_val(&cap);
+        match name {
+            "real" => meas_items.insert(MeasItem::Real, v),
+            "user" => meas_items.insert(MeasItem::User, v),
+            "sys" => meas_items.insert(MeasItem::Sys, v),
+            _ => meas_items.insert(MeasItem::Unknown(String::from(name)), v),
+        };
+    }
+    const MINIMUM_ITEM_COUNT: usize = 3;
+    if meas_items.len() < MINIMUM_ITEM_COUNT {
+        meas_items.clear();
+    }
+    meas_items
+}
+
 fn builtin_re() -> &'static regex::Regex {
     static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
     RE.get_or_init(|| {
@@ -287,19 +530,112 @@ fn builtin_re() -> &'static regex::Regex {
     })
 }
 
+/// Parse the output of TIMEFORMAT of bash and TIMEFMT of zsh set by try_new_builtin_time.
+fn parse_timefmt_meas_items(err_msg: &str) -> HashMap<MeasItem, f64> {
+    let mut meas_items = HashMap::<MeasItem, f64>::new();
+    let re = timefmt_re();
+    const KB: f64 = 1024.0;
+    for cap in re.captures_iter(err_msg) {
+        let name = &cap["name"];
+        let mut v: f64 = cap["val"].parse().unwrap();
+        if cap.name("unit").is_some_and(|x| x.as_str() == "ms") {
+            v /= 1000.0;
+        }
+        match name {
+            "real" => meas_items.insert(MeasItem::Real, v),
+            "user" => meas_items.insert(MeasItem::User, v),
+            "sys" => meas_items.insert(MeasItem::Sys, v),
+            "cpu" => meas_items.insert(MeasItem::CpuUsage, v),
+            "maxrss" => meas_items.insert(MeasItem::MaxResident, v * KB),
+            "avgtext" => meas_items.insert(MeasItem::AvgSharedText, v * KB),
+            "avgdata" => meas_items.insert(MeasItem::AvgUnsharedData, v * KB),
+            "avgtotal" => meas_items.insert(MeasItem::AvgTotal, v * KB),
+            "majflt" => meas_items.insert(MeasItem::MajorPageFault, v),
+            "minflt" => meas_items.insert(MeasItem::MinorPageFault, v),
+            "nswap" => meas_items.insert(MeasItem::Swap, v),
+            "inblock" => meas_items.insert(MeasItem::BlockInput, v),
+            "oublock" => meas_items.insert(MeasItem::BlockOutput, v),
+            "msgsnd" => meas_items.insert(MeasItem::MsgSend, v),
+            "msgrcv" => meas_items.insert(MeasItem::MsgRecv, v),
+            "nsignals" => meas_items.insert(MeasItem::SignalRecv, v),
+            "nvcsw" => meas_items.insert(MeasItem::VoluntaryCtxSwitch, v),
+            "nivcsw" => meas_items.insert(MeasItem::InvoluntaryCtxSwitch, v),
+            _ => meas_items.insert(MeasItem::Unknown(String::from(name)), v),
+        };
+    }
+    const MINIMUM_ITEM_COUNT: usize = 4; // real, user, sys and cpu at least, to be distinguished from the plain one.
+    if meas_items.len() < MINIMUM_ITEM_COUNT {
+        meas_items.clear();
+    }
+    meas_items
+}
+
+fn timefmt_re() -> &'static regex::Regex {
+    static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
+    RE.get_or_init(|| {
+        regex::Regex::new(r"(?m)^\s*(?P<name>[a-z]+) (?P<val>[\d.]+)(?P<unit>ms|%)?\s*$").unwrap()
+    })
+}
+
+/// Parse the output of the fish time, whose units change with the value.
+fn parse_fish_meas_items(err_msg: &str) -> HashMap<MeasItem, f64> {
+    let mut meas_items = HashMap::<MeasItem, f64>::new();
+    let re = fish_re();
+    for cap in re.captures_iter(err_msg) {
+        let v: f64 = cap["val"].parse().unwrap();
+        let v = v * match &cap["unit"] {
+            "micros" => 0.000_001,
+            "millis" => 0.001,
+            "mins" => 60.0,
+            "hours" => 60.0 * 60.0,
+            _ => 1.0,
+        };
+        match &cap["name"] {
+            "Executed in" => meas_items.insert(MeasItem::Real, v),
+            "usr time" => meas_items.insert(MeasItem::User, v),
+            "sys time" => meas_items.insert(MeasItem::Sys, v),
+            name => meas_items.insert(MeasItem::Unknown(String::from(name)), v),
+        };
+    }
+    const MINIMUM_ITEM_COUNT: usize = 3;
+    if meas_items.len() < MINIMUM_ITEM_COUNT {
+        meas_items.clear();
+    }
+    meas_items
+}
+
+fn fish_re() -> &'static regex::Regex {
+    static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
+    RE.get_or_init(|| {
+        // Only the first value is used, which is the total of fish and external.
+        regex::Regex::new(r"(?m)^\s*(?P<name>Executed in|usr time|sys time)\s+(?P<val>[\d.]+) (?P<unit>micros|millis|secs|mins|hours)")
+            .unwrap()
+    })
+}
+
+/// Candidates of the BSD time in order of fallback.
+pub fn bsd_time_candidates(benchmark: &crate::benchmark::Benchmark) -> Vec<BackendFactory<'_>> {
+    [false, true]
+        .into_iter()
+        .map(|fallback_sh| -> BackendFactory {
+            Box::new(move || Ok(Box::new(try_new_bsd_time(benchmark, fallback_sh)?)))
+        })
+        .collect()
+}
+
 pub fn try_new_bsd_time(
-    cli_args: &crate::cli_args::CliArgs,
+    benchmark: &crate::benchmark::Benchmark,
     fallback_sh: bool,
 ) -> anyhow::Result<TimeCmd> {
     TimeCmd::try_new_with_command(
         &if fallback_sh {
             "sh".to_string()
         } else {
-            cli_args.shell.clone()
+            benchmark.shell.clone()
         },
-        &cli_args.shell_arg,
-        CmdType::Bsd,
-        &cli_args.bsd,
+        &benchmark.shell_arg,
+        "bsd",
+        &benchmark.bsd,
         |err_msg| {
             let mut meas_items = HashMap::<MeasItem, f64>::new();
             let re = bsd_re();
@@ -350,8 +686,24 @@ fn bsd_re() -> &'static regex::Regex {
     })
 }
 
+/// Candidates of the GNU time in order of fallback.
+pub fn gnu_time_candidates(benchmark: &crate::benchmark::Benchmark) -> Vec<BackendFactory<'_>> {
+    [(false, false), (false, true), (true, false), (true, true)]
+        .into_iter()
+        .map(|(fallback_sh, fallback_time)| -> BackendFactory {
+            Box::new(move || {
+                Ok(Box::new(try_new_gnu_time(
+                    benchmark,
+                    fallback_sh,
+                    fallback_time,
+                )?))
+            })
+        })
+        .collect()
+}
+
 pub fn try_new_gnu_time(
-    cli_args: &crate::cli_args::CliArgs,
+    benchmark: &crate::benchmark::Benchmark,
     fallback_sh: bool,
     fallback_time: bool,
 ) -> anyhow::Result<TimeCmd> {
@@ -359,107 +711,155 @@ pub fn try_new_gnu_time(
         &if fallback_sh {
             "sh".to_string()
         } else {
-            cli_args.shell.clone()
+            benchmark.shell.clone()
         },
-        &cli_args.shell_arg,
-        CmdType::Gnu,
+        &benchmark.shell_arg,
+        "gnu",
         &if fallback_time {
             "/usr/bin/env time -v".to_string()
         } else {
-            cli_args.gnu.clone()
+            benchmark.gnu.clone()
         },
-        |err_msg| {
-            let mut meas_items = HashMap::<MeasItem, f64>::new();
-            let re = gnu_re();
-            const KB: f64 = 1024.0;
-            for cap in re.captures_iter(err_msg) {
-                let (name, v) = capture_name_and_val(&cap);
-                match name {
-                    "Command being timed" => {}
-                    "User time (seconds)" => {
-                        meas_items.insert(MeasItem::User, v);
-                    }
-                    "System time (seconds)" => {
-                        meas_items.insert(MeasItem::Sys, v);
-                    }
-                    "Percent of CPU this job got" => {
-                        meas_items.insert(MeasItem::CpuUsage, v);
-                    }
-                    "Elapsed (wall clock) time (h:mm:ss or m:ss)" => {
-                        meas_items.insert(MeasItem::Real, v);
-                    }
-                    "Average shared text size (kbytes)" => {
-                        meas_items.insert(MeasItem::AvgSharedText, v * KB);
-                    }
-                    "Average unshared data size (kbytes)" => {
-                        meas_items.insert(MeasItem::AvgUnsharedData, v * KB);
-                    }
-                    "Average stack size (kbytes)" => {
-                        meas_items.insert(MeasItem::AvgStack, v * KB);
-                    }
-                    "Average total size (kbytes)" => {
-                        meas_items.insert(MeasItem::AvgTotal, v * KB);
-                    }
-                    "Maximum resident set size (kbytes)" => {
-                        meas_items.insert(MeasItem::MaxResident, v * KB);
-                    }
-                    "Average resident set size (kbytes)" => {
-                        meas_items.insert(MeasItem::AvgResident, v * KB);
-                    }
-                    "Major (requiring I/O) page faults" => {
-                        meas_items.insert(MeasItem::MajorPageFault, v);
-                    }
-                    "Minor (reclaiming a frame) page faults" => {
-                        meas_items.insert(MeasItem::MinorPageFault, v);
-                    }
-                    "Voluntary context switches" => {
-                        meas_items.insert(MeasItem::VoluntaryCtxSwitch, v);
-                    }
-                    "Involuntary context switches" => {
-                        meas_items.insert(MeasItem::InvoluntaryCtxSwitch, v);
-                    }
-                    "Swaps" => {
-                        meas_items.insert(MeasItem::Swap, v);
-                    }
-                    "File system inputs" => {
-                        meas_items.insert(MeasItem::BlockInput, v);
-                    }
-                    "File system outputs" => {
-                        meas_items.insert(MeasItem::BlockOutput, v);
-                    }
-                    "Socket messages sent" => {
-                        meas_items.insert(MeasItem::MsgSend, v);
-                    }
-                    "Socket messages received" => {
-                        meas_items.insert(MeasItem::MsgRecv, v);
-                    }
-                    "Signals delivered" => {
-                        meas_items.insert(MeasItem::SignalRecv, v);
-                    }
-                    "Page size (bytes)" => {
-                        meas_items.insert(MeasItem::Page, v);
-                    }
-                    "Exit status" => {
-                        meas_items.insert(MeasItem::ExitStatus, v);
-                    }
-                    _ => {
-                        meas_items.insert(MeasItem::Unknown(String::from(name)), v);
-                    }
-                };
+        parse_gnu_meas_items,
+    )
+}
+
+/// Empty if the elapsed time is not found, such as of BusyBox run by `/usr/bin/env time -v` on Alpine,
+/// so that it is not accepted as GNU.
+fn parse_gnu_meas_items(err_msg: &str) -> HashMap<MeasItem, f64> {
+    let mut meas_items = parse_verbose_meas_items(err_msg, gnu_re());
+    if !meas_items.contains_key(&MeasItem::Real) {
+        meas_items.clear();
+    }
+    meas_items
+}
+
+/// Parse the output of `time -v`, whose item names are common to GNU and BusyBox.
+fn parse_verbose_meas_items(err_msg: &str, re: &regex::Regex) -> HashMap<MeasItem, f64> {
+    let mut meas_items = HashMap::<MeasItem, f64>::new();
+    const KB: f64 = 1024.0;
+    for cap in re.captures_iter(err_msg) {
+        let (name, v) = capture_name_and_val(&cap);
+        match name {
+            "Command being timed" => {}
+            "User time (seconds)" => {
+                meas_items.insert(MeasItem::User, v);
             }
-            const MINIMUM_ITEM_COUNT: usize = 3; // Minimum 3 to allow any change except real, user, and sys.
-            if meas_items.len() < MINIMUM_ITEM_COUNT {
-                meas_items.clear();
+            "System time (seconds)" => {
+                meas_items.insert(MeasItem::Sys, v);
             }
-            meas_items
+            "Percent of CPU this job got" => {
+                meas_items.insert(MeasItem::CpuUsage, v);
+            }
+            "Elapsed (wall clock) time (h:mm:ss or m:ss)" => {
+                meas_items.insert(MeasItem::Real, v);
+            }
+            "Average shared text size (kbytes)" => {
+                meas_items.insert(MeasItem::AvgSharedText, v * KB);
+            }
+            "Average unshared data size (kbytes)" => {
+                meas_items.insert(MeasItem::AvgUnsharedData, v * KB);
+            }
+            "Average stack size (kbytes)" => {
+                meas_items.insert(MeasItem::AvgStack, v * KB);
+            }
+            "Average total size (kbytes)" => {
+                meas_items.insert(MeasItem::AvgTotal, v * KB);
+            }
+            "Maximum resident set size (kbytes)" => {
+                meas_items.insert(MeasItem::MaxResident, v * KB);
+            }
+            "Average resident set size (kbytes)" => {
+                meas_items.insert(MeasItem::AvgResident, v * KB);
+            }
+            "Major (requiring I/O) page faults" => {
+                meas_items.insert(MeasItem::MajorPageFault, v);
+            }
+            "Minor (reclaiming a frame) page faults" => {
+                meas_items.insert(MeasItem::MinorPageFault, v);
+            }
+            "Voluntary context switches" => {
+                meas_items.insert(MeasItem::VoluntaryCtxSwitch, v);
+            }
+            "Involuntary context switches" => {
+                meas_items.insert(MeasItem::InvoluntaryCtxSwitch, v);
+            }
+            "Swaps" => {
+                meas_items.insert(MeasItem::Swap, v);
+            }
+            "File system inputs" => {
+                meas_items.insert(MeasItem::BlockInput, v);
+            }
+            "File system outputs" => {
+                meas_items.insert(MeasItem::BlockOutput, v);
+            }
+            "Socket messages sent" => {
+                meas_items.insert(MeasItem::MsgSend, v);
+            }
+            "Socket messages received" => {
+                meas_items.insert(MeasItem::MsgRecv, v);
+            }
+            "Signals delivered" => {
+                meas_items.insert(MeasItem::SignalRecv, v);
+            }
+            "Page size (bytes)" => {
+                meas_items.insert(MeasItem::Page, v);
+            }
+            "Exit status" => {
+                meas_items.insert(MeasItem::ExitStatus, v);
+            }
+            _ => {
+                meas_items.insert(MeasItem::Unknown(String::from(name)), v);
+            }
+        };
+    }
+    const MINIMUM_ITEM_COUNT: usize = 3; // Minimum 3 to allow any change except real, user, and sys.
+    if meas_items.len() < MINIMUM_ITEM_COUNT {
+        meas_items.clear();
+    }
+    meas_items
+}
+
+fn gnu_re() -> &'static regex::Regex {
+    static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
+    RE.get_or_init(|| {
+        // The value must end the line, so that the elapsed time of BusyBox such as `0m 0.00s` is not taken as 0.
+        regex::Regex::new(r"(?m)\s*(?P<name>[\w ():/]+): ((?P<hour>\d+)??:?(?P<min>\d+):(?P<sec>[\d.]+)|(?P<val>[\d.]+)%?)[ \t]*$").unwrap()
+    })
+}
+
+/// Candidates of the BusyBox time in order of fallback.
+pub fn busybox_time_candidates(benchmark: &crate::benchmark::Benchmark) -> Vec<BackendFactory<'_>> {
+    [false, true]
+        .into_iter()
+        .map(|fallback_sh| -> BackendFactory {
+            Box::new(move || Ok(Box::new(try_new_busybox_time(benchmark, fallback_sh)?)))
+        })
+        .collect()
+}
+
+pub fn try_new_busybox_time(
+    benchmark: &crate::benchmark::Benchmark,
+    fallback_sh: bool,
+) -> anyhow::Result<TimeCmd> {
+    TimeCmd::try_new_with_command(
+        &if fallback_sh {
+            "sh".to_string()
+        } else {
+            benchmark.shell.clone()
         },
+        &benchmark.shell_arg,
+        "busybox",
+        &benchmark.busybox,
+        |err_msg| parse_verbose_meas_items(err_msg, busybox_re()),
     )
 }
 
-fn gnu_re() -> &'static regex::Regex {
+fn busybox_re() -> &'static regex::Regex {
     static RE: once_cell::sync::OnceCell<regex::Regex> = once_cell::sync::OnceCell::new();
     RE.get_or_init(|| {
-        regex::Regex::new(r"\s*(?P<name>[\w ():/]+): ((?P<hour>\d+)??:?(?P<min>\d+):(?P<sec>[\d.]+)|(?P<val>[\d.]+))").unwrap()   
+        // Elapsed time is such as `1h 2m 03s` or `2m 3.45s` unlike GNU.
+        regex::Regex::new(r"\s*(?P<name>[\w ():/]+): ((?:(?P<hour>\d+)h )?(?P<min>\d+)m (?P<sec>[\d.]+)s|(?P<val>[\d.]+))").unwrap()
     })
 }
 
@@ -467,26 +867,70 @@ impl TimeCmd {
     /// Start checking if the time command is available.
     fn try_new_with_command(
         sh: &str,
-        sh_arg: &String,
-        cmd_type: CmdType,
-        command: &String,
-        parse_meas_items: fn(&str) -> HashMap<MeasItem, f64>,
+        sh_arg: &str,
+        name: &'static str,
+        command: &str,
+        parse_meas_items: ParseMeasItems,
     ) -> anyhow::Result<Self> {
         Ok(Self {
             sh: sh.to_owned(),
-            sh_arg: sh_arg.clone(),
-            cmd_type,
-            command: command.clone(),
+            sh_arg: sh_arg.to_owned(),
+            name,
+            command: command.to_owned(),
             parse_meas_items,
             // test to use
-            process: execute(sh, &[sh_arg, format!("{} true", command).as_str()])?,
+            process: execute(
+                sh,
+                &[sh_arg, format!("{} true", command).as_str()],
+                &ExecOptions::default(),
+            )?,
             ready_status: ReadyStatus::Checking,
             meas_report: None,
+            stdout: None,
+            output: None,
         })
     }
+}
+
+impl MeasureBackend for TimeCmd {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    /// The BSD time has no option to show the version, so it is None.
+    fn version(&self) -> Option<String> {
+        let mut words = self.command.split_whitespace();
+        let (program, args): (&str, &[&str]) = match self.name {
+            "gnu" => {
+                let program = words.next()?;
+                // Such as `/usr/bin/env time`.
+                if program.ends_with("env") {
+                    (words.next()?, &["--version"])
+                } else {
+                    (program, &["--version"])
+                }
+            }
+            // busybox shows the version at the first line of the usage.
+            "busybox" => (words.next()?, &[]),
+            "builtin" => (self.sh.as_str(), &["--version"]),
+            _ => return None,
+        };
+        let output = std::process::Command::new(program)
+            .args(args)
+            .stdin(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()?;
+        // The exit status is not checked, because busybox without arguments fails.
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .map(|x| x.trim().to_string())
+            .filter(|x| !x.is_empty())
+    }
 
     /// Check if time command is available.
-    pub fn ready_status(&mut self) -> ReadyStatus {
+    fn ready_status(&mut self) -> ReadyStatus {
         if self.ready_status == ReadyStatus::Checking && self.is_finished() {
             let err_msg = stderr(&mut self.process);
             if (self.parse_meas_items)(err_msg.as_str()).is_empty() {
@@ -499,66 +943,156 @@ impl TimeCmd {
     }
 
     /// Start measurement.
-    pub fn execute(&mut self, command: &str) -> anyhow::Result<()> {
+    fn execute(&mut self, command: &str, options: &ExecOptions) -> anyhow::Result<()> {
         anyhow::ensure!(self.ready_status == ReadyStatus::Ready, CmdError::NotReady);
 
         self.meas_report = None;
+        self.output = None;
         self.process = execute(
             self.sh.as_str(),
             &[
                 self.sh_arg.as_str(),
                 format!("{} {}", self.command, command).as_str(),
             ],
+            options,
         )?;
+        self.stdout = read_in_background(self.process.stdout.take());
         Ok(())
     }
 
     /// Check if measurement has been finished.
-    pub fn is_finished(&mut self) -> bool {
+    fn is_finished(&mut self) -> bool {
         self.process.try_wait().unwrap().is_some()
     }
 
     /// Get the output result of time command.
-    pub fn get_report(&mut self) -> anyhow::Result<&HashMap<MeasItem, f64>> {
+    fn get_report(&mut self) -> anyhow::Result<&HashMap<MeasItem, f64>> {
         anyhow::ensure!(self.is_finished(), CmdError::NotFinished);
 
-        if self.meas_report.is_some() {
-            return Ok(self.meas_report.as_ref().unwrap());
+        if let Some(ref meas_report) = self.meas_report {
+            return Ok(meas_report);
         }
 
         let err_msg = stderr(&mut self.process);
+        if let Some(stdout) = self.stdout.take() {
+            self.output = Some(join_output(stdout) + &err_msg);
+        }
         let mut meas_items = (self.parse_meas_items)(err_msg.as_str());
         if meas_items.is_empty() {
             Err(CmdError::ParseError("time").into())
         } else {
-            if meas_items.get(&MeasItem::ExitStatus).is_none() {
-                meas_items.insert(
-                    MeasItem::ExitStatus,
-                    self.process.wait().unwrap().code().unwrap_or_default() as f64,
-                );
-            }
+            meas_items
+                .entry(MeasItem::ExitStatus)
+                .or_insert_with(|| self.process.wait().unwrap().code().unwrap_or_default() as f64);
             self.meas_report = Some(meas_items);
             Ok(self.meas_report.as_ref().unwrap())
         }
     }
 
-    pub fn kill(&mut self) -> anyhow::Result<()> {
+    fn kill(&mut self) -> anyhow::Result<()> {
         self.process.kill().context("Could not kill time process.")
     }
+
+    fn get_output(&mut self) -> anyhow::Result<String> {
+        self.get_report()?;
+        Ok(self.output.clone().unwrap_or_default())
+    }
+
+    fn pid(&self) -> Option<u32> {
+        Some(self.process.id())
+    }
 }
 
 /// Execute program.
-fn execute(program: &str, args: &[&str]) -> anyhow::Result<std::process::Child> {
-    std::process::Command::new(program)
-        .args(args)
-        .stdout(std::process::Stdio::null())
-        .stderr(std::process::Stdio::piped())
+fn execute(
+    program: &str,
+    args: &[&str],
+    options: &ExecOptions,
+) -> anyhow::Result<std::process::Child> {
+    spawn(
+        &mut new_command(program, args, options),
+        stdout(options),
+        std::process::Stdio::piped(),
+    )
+}
+
+/// Make the program command with options applied.
+pub(crate) fn new_command(
+    program: &str,
+    args: &[&str],
+    options: &ExecOptions,
+) -> std::process::Command {
+    let mut command = std::process::Command::new(program);
+    command.args(args).envs(options.envs.iter().cloned());
+    if let Some(cwd) = &options.cwd {
+        command.current_dir(cwd);
+    }
+    #[cfg(target_os = "linux")]
+    if let Some(cgroup) = &options.cgroup {
+        move_into_cgroup(&mut command, cgroup);
+    }
+    #[cfg(target_os = "linux")]
+    if options.perf_counters {
+        crate::perf::attach(&mut command);
+    }
+    crate::sched::apply(&mut command, options);
+    command
+}
+
+/// Move the process into the cgroup between fork and exec, so that all its descendants are included.
+#[cfg(target_os = "linux")]
+fn move_into_cgroup(command: &mut std::process::Command, cgroup: &std::path::Path) {
+    use std::os::unix::{ffi::OsStrExt as _, process::CommandExt as _};
+    // The path is prepared beforehand, because allocation is not safe after fork.
+    let procs = std::ffi::CString::new(cgroup.join("cgroup.procs").as_os_str().as_bytes())
+        .unwrap_or_default();
+    // SAFETY: Only async-signal-safe functions are called in the closure.
+    unsafe {
+        command.pre_exec(move || {
+            let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+            if fd < 0 {
+                return Err(std::io::Error::last_os_error());
+            }
+            // "0" means the writing process itself.
+            let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+            let err = std::io::Error::last_os_error();
+            libc::close(fd);
+            if written < 0 {
+                return Err(err);
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Stdout of the command, which is discarded unless capture_output.
+pub(crate) fn stdout(options: &ExecOptions) -> std::process::Stdio {
+    if options.capture_output {
+        std::process::Stdio::piped()
+    } else {
+        std::process::Stdio::null()
+    }
+}
+
+/// Spawn the command.
+pub(crate) fn spawn(
+    command: &mut std::process::Command,
+    stdout: std::process::Stdio,
+    stderr: std::process::Stdio,
+) -> anyhow::Result<std::process::Child> {
+    command
+        .stdout(stdout)
+        .stderr(stderr)
         .spawn()
         .with_context(|| {
             format!(
                 "Could not start `{}` execution with argument `{}`",
-                program,
-                args.join(" ")
+                command.get_program().to_string_lossy(),
+                command
+                    .get_args()
+                    .map(|x| x.to_string_lossy())
+                    .collect::<Vec<_>>()
+                    .join(" ")
             )
         })
 }
@@ -575,6 +1109,26 @@ fn stderr(child: &mut std::process::Child) -> String {
     msg
 }
 
+/// Read the pipe in another thread, so that the process is not blocked by the full pipe.
+pub(crate) fn read_in_background<R>(pipe: Option<R>) -> Option<std::thread::JoinHandle<String>>
+where
+    R: Read + Send + 'static,
+{
+    pipe.map(|mut pipe| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            // The output is used only for extracting metrics, so read as much as possible.
+            let _ = pipe.read_to_end(&mut buf);
+            String::from_utf8_lossy(&buf).into_owned()
+        })
+    })
+}
+
+/// Wait for the output read by read_in_background().
+pub(crate) fn join_output(handle: std::thread::JoinHandle<String>) -> String {
+    handle.join().unwrap_or_default()
+}
+
 /// Capture a mix of time and value types.
 fn capture_name_and_val<'a>(cap: &'a regex::Captures) -> (&'a str, f64) {
     let v = if let Some(sec_match) = cap.name("sec") {
@@ -763,6 +1317,144 @@ mod test {
         );
     }
 
+    #[test]
+    fn name_max_width() {
+        let builtin = meas_item_name_max_width(&[], 1);
+        assert!(meas_item_name(&MeasItem::Real, 1).len() <= builtin);
+        let metric = MeasItem::Metric(
+            "[cc1plus] peak RSS of a very long name".repeat(2),
+            crate::metric::MetricUnit::Bytes,
+        );
+        assert_eq!(
+            meas_item_name_max_width(std::slice::from_ref(&metric), 1),
+            meas_item_name(&metric, 1).chars().count()
+        );
+        assert!(builtin < meas_item_name_max_width(&[metric], 1));
+        assert!(builtin < meas_item_name_max_width(&[], 100));
+    }
+
+    #[test]
+    fn gnu_parse_rejects_busybox() {
+        let output = r#"
+            Command being timed: "true"
+            User time (seconds): 0.00
+            System time (seconds): 0.00
+            Percent of CPU this job got: 0%
+            Elapsed (wall clock) time (h:mm:ss or m:ss): 0m 0.00s
+            Maximum resident set size (kbytes): 1024
+            Minor (reclaiming a frame) page faults: 52
+            Exit status: 0
+        "#;
+        assert!(!gnu_re()
+            .captures_iter(output)
+            .any(|cap| &cap["name"] == "Elapsed (wall clock) time (h:mm:ss or m:ss)"));
+        assert!(parse_gnu_meas_items(output).is_empty());
+        assert_eq!(
+            parse_verbose_meas_items(output, busybox_re())[&MeasItem::Real],
+            0.0
+        );
+    }
+
+    #[test]
+    fn busybox_parse() {
+        let output = r#"
+            Command being timed: "sleep 1"
+            User time (seconds): 0.01
+            System time (seconds): 0.02
+            Percent of CPU this job got: 3%
+            Elapsed (wall clock) time (h:mm:ss or m:ss): 10m 1.00s
+            Maximum resident set size (kbytes): 1248
+            Minor (reclaiming a frame) page faults: 152
+            Exit status: 18
+        "#;
+        let actually = parse_verbose_meas_items(output, busybox_re());
+        assert_eq!(actually.len(), 7);
+        assert_eq!(actually[&MeasItem::Real], 601.0);
+        assert_eq!(actually[&MeasItem::User], 0.01);
+        assert_eq!(actually[&MeasItem::CpuUsage], 3.0);
+        assert_eq!(actually[&MeasItem::MaxResident], 1248.0 * 1024.0);
+        assert_eq!(actually[&MeasItem::ExitStatus], 18.0);
+
+        let output = "Elapsed (wall clock) time (h:mm:ss or m:ss): 1h 2m 03s";
+        let cap = busybox_re().captures(output).unwrap();
+        assert_eq!(capture_name_and_val(&cap).1, 3723.0);
+    }
+
+    #[test]
+    fn timefmt_parse_bash() {
+        let output = "real 1.007\nuser 0.100\nsys 0.001\ncpu 10.03\n";
+        let actually = parse_timefmt_meas_items(output);
+        let expected = HashMap::from([
+            (MeasItem::Real, 1.007),
+            (MeasItem::User, 0.1),
+            (MeasItem::Sys, 0.001),
+            (MeasItem::CpuUsage, 10.03),
+        ]);
+        assert_eq!(expected, actually);
+
+        // The default format of bash is left to the plain one.
+        let output = "real\t0m1.007s\nuser\t0m0.100s\nsys\t0m0.001s\n";
+        assert!(parse_timefmt_meas_items(output).is_empty());
+        assert_eq!(parse_builtin_meas_items(output).len(), 3);
+    }
+
+    #[test]
+    fn timefmt_parse_zsh() {
+        let output = r#"
+            real 1007ms
+            user 100ms
+            sys 1ms
+            cpu 10%
+            maxrss 1248
+            avgtext 0
+            avgdata 0
+            avgtotal 0
+            majflt 10
+            minflt 152
+            nswap 0
+            inblock 13
+            oublock 14
+            msgsnd 0
+            msgrcv 0
+            nsignals 0
+            nvcsw 11
+            nivcsw 6
+        "#;
+        let actually = parse_timefmt_meas_items(output);
+        assert_eq!(actually.len(), 18);
+        assert_eq!(actually[&MeasItem::Real], 1.007);
+        assert_eq!(actually[&MeasItem::User], 0.1);
+        assert_eq!(actually[&MeasItem::Sys], 0.001);
+        assert_eq!(actually[&MeasItem::CpuUsage], 10.0);
+        assert_eq!(actually[&MeasItem::MaxResident], 1248.0 * 1024.0);
+        assert_eq!(actually[&MeasItem::MajorPageFault], 10.0);
+        assert_eq!(actually[&MeasItem::MinorPageFault], 152.0);
+        assert_eq!(actually[&MeasItem::BlockOutput], 14.0);
+        assert_eq!(actually[&MeasItem::InvoluntaryCtxSwitch], 6.0);
+
+        // The default format of zsh.
+        let output = "true  0.00s user 0.00s system 0% cpu 0.001 total";
+        assert!(parse_timefmt_meas_items(output).is_empty());
+    }
+
+    #[test]
+    fn fish_parse() {
+        let output = r#"
+________________________________________________________
+Executed in    1.01 secs    fish           external
+   usr time  100.00 micros    0.00 micros  100.00 micros
+   sys time    2.50 millis    0.00 millis    2.50 millis
+        "#;
+        let actually = parse_fish_meas_items(output);
+        assert_eq!(actually.len(), 3);
+        assert_eq!(actually[&MeasItem::Real], 1.01);
+        assert!((actually[&MeasItem::User] - 0.0001).abs() < 1e-12);
+        assert!((actually[&MeasItem::Sys] - 0.0025).abs() < 1e-12);
+
+        let output = "Executed in    2.00 mins    fish           external";
+        assert!(parse_fish_meas_items(output).is_empty());
+    }
+
     #[test]
     fn meas_item_unit_value_sec() {
         assert_eq!(
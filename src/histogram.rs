@@ -0,0 +1,170 @@
+// Copyright © ArkBig
+//! This file provides the histogram and box plot in text by `--histogram`.
+//!
+//! Mean ± σ hides bimodal distributions, so the shape of the samples is drawn after each report.
+//! The box plot spans the Hampel control limits (LCL and UCL) used for the outlier detection.
+
+use crate::cmd::{meas_item_name, meas_item_unit_value, MeasItem};
+use crate::stats::Stats;
+
+/// Maximum number of bins, even with many runs.
+const MAX_BINS: usize = 20;
+/// Partial blocks of 1/8 to 7/8 width.
+const PARTIAL_BLOCKS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Lines of the histogram and box plot of the samples, fit in width columns.
+///
+/// Empty if there are no samples.
+pub fn lines(item: &MeasItem, samples: &[f64], loops: u16, width: usize) -> Vec<String> {
+    let stats = Stats::new(samples);
+    if stats.count() == 0 {
+        return Vec::new();
+    }
+    let unit_value = |val: f64| meas_item_unit_value(item, val, loops);
+    let mut lines = vec![format!("{}:", meas_item_name(item, loops).trim_end())];
+
+    // Sturges' formula.
+    let bins = if stats.min() < stats.max() {
+        ((stats.count() as f64).log2().ceil() as usize + 1).min(MAX_BINS)
+    } else {
+        1
+    };
+    let counts = stats.histogram(bins);
+    let bin_width = (stats.max() - stats.min()) / bins as f64;
+    let ranges: Vec<_> = (0..bins)
+        .map(|i| {
+            let lower = stats.min() + bin_width * i as f64;
+            if bins == 1 {
+                format!("x = {}", unit_value(lower))
+            } else if i + 1 == bins {
+                format!("{} ≦ x ≦ {}", unit_value(lower), unit_value(stats.max()))
+            } else {
+                format!(
+                    "{} ≦ x < {}",
+                    unit_value(lower),
+                    unit_value(lower + bin_width)
+                )
+            }
+        })
+        .collect();
+    let range_width = ranges.iter().map(|x| x.chars().count()).max().unwrap_or(0);
+    let max_count = counts.iter().copied().max().unwrap_or(0);
+    let count_width = max_count.to_string().len();
+    // "  {range} │{bar} {count}"
+    let bar_width = width.saturating_sub(range_width + count_width + 5).max(10);
+    for (range, &count) in ranges.iter().zip(&counts) {
+        lines.push(format!(
+            "  {:>range_width$} │{} {}",
+            range,
+            bar(count as f64 / max_count as f64 * bar_width as f64),
+            count,
+        ));
+    }
+
+    let plot_width = width.saturating_sub(4).max(10);
+    lines.push(format!("  {}", box_plot(&stats, samples, plot_width)));
+    let lcl = format!("LCL {}", unit_value(stats.lcl));
+    let ucl = format!("UCL {}", unit_value(stats.ucl));
+    lines.push(format!(
+        "  {}{:>pad$}",
+        lcl,
+        ucl,
+        pad = plot_width.saturating_sub(lcl.chars().count())
+    ));
+    lines
+}
+
+/// Horizontal bar of the length in columns, with a partial block at the end.
+fn bar(len: f64) -> String {
+    let eighths = (len * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if eighths % 8 != 0 {
+        bar.push(PARTIAL_BLOCKS[eighths % 8 - 1]);
+    }
+    bar
+}
+
+/// One line box plot from LCL to UCL, or to the outliers beyond them.
+///
+/// `¦` is LCL and UCL, `├─` and `─┤` are the min and max excluding outliers,
+/// `▓` is from the first to the third quartile, `┃` is the median and `•` are the outliers.
+fn box_plot(stats: &Stats, samples: &[f64], width: usize) -> String {
+    let lower = stats.lcl.min(stats.min());
+    let upper = stats.ucl.max(stats.max());
+    let pos = |val: f64| {
+        if lower < upper {
+            ((val - lower) / (upper - lower) * (width - 1) as f64).round() as usize
+        } else {
+            width / 2
+        }
+    };
+    let mut chars = vec![' '; width];
+    // The samples are drawn over the limits, such as if all samples are the same.
+    chars[pos(stats.lcl)] = '¦';
+    chars[pos(stats.ucl)] = '¦';
+    let whisker_lower = pos(stats.min_excluding_outlier());
+    let whisker_upper = pos(stats.max_excluding_outlier());
+    for c in &mut chars[whisker_lower..=whisker_upper] {
+        *c = '─';
+    }
+    chars[whisker_lower] = '├';
+    chars[whisker_upper] = '┤';
+    for c in &mut chars[pos(stats.quantile(0.25))..=pos(stats.quantile(0.75))] {
+        *c = '▓';
+    }
+    chars[pos(stats.median())] = '┃';
+    for &val in samples.iter().filter(|x| x.is_finite()) {
+        if stats.is_outlier(val) {
+            chars[pos(val)] = '•';
+        }
+    }
+    chars.into_iter().collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn histogram_bar() {
+        assert_eq!(bar(0.0), "");
+        assert_eq!(bar(2.5), "██▌");
+        assert_eq!(bar(0.125), "▏");
+    }
+
+    #[test]
+    fn histogram_lines() {
+        // Bimodal.
+        let samples = [1.0, 1.1, 1.0, 1.1, 3.0, 3.1, 3.0, 3.1];
+        let output = lines(&MeasItem::Real, &samples, 1, 60);
+        assert_eq!(output[0], "Elapsed (wall clock) time:");
+        // 4 bins by Sturges' formula, and 2 lines of the box plot.
+        assert_eq!(output.len(), 1 + 4 + 2);
+        assert!(output[1].contains(" 1 sec ≦ x < 1.525 sec │"));
+        assert!(output[1].ends_with(" 4"));
+        assert!(output[2].ends_with("│ 0"));
+        assert!(output[4].contains("≦ x ≦ 3.1 sec │"));
+        for line in &output {
+            assert!(line.chars().count() <= 60, "{}", line);
+        }
+        assert!(output[5].contains('┃'));
+        assert!(output[6].starts_with("  LCL "));
+        assert!(output[6].contains("UCL "));
+
+        let output = lines(&MeasItem::Real, &[2.0, 2.0], 1, 60);
+        assert_eq!(output[1], format!("  x = 2 sec │{} 2", "█".repeat(45)));
+        assert!(output[2].contains('┃'));
+        assert!(lines(&MeasItem::Real, &[], 1, 60).is_empty());
+    }
+
+    #[test]
+    fn histogram_box_plot() {
+        let samples = [0.0, 3.0, 2.9, 3.1, 2.95, 3.05, 10.0];
+        let plot = box_plot(&Stats::new(&samples), &samples, 40);
+        assert_eq!(plot.chars().count(), 40);
+        assert_eq!(plot.matches('•').count(), 1);
+        assert_eq!(plot.matches('¦').count(), 2);
+        assert!(plot.contains('┃'));
+        assert!(plot.ends_with('•'));
+    }
+}
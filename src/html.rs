@@ -0,0 +1,701 @@
+// Copyright © ArkBig
+//! This file provides the HTML report with the charts by `--export-html`.
+//!
+//! The charts are inline SVG and the style is embedded, so the report is a single file
+//! without any external assets, to be attached to release notes and issues.
+
+use std::fmt::Write as _;
+
+use crate::benchmark::{BenchmarkResult, CommandResult};
+use crate::cmd::{meas_item_name, meas_item_unit_value, MeasItem};
+use crate::reporter::Reporter;
+use crate::stats::Stats;
+
+/// Items to chart, the time and the memory.
+pub(crate) const CHART_ITEMS: [MeasItem; 2] = [MeasItem::Real, MeasItem::MaxResident];
+/// Colors of the commands in order, repeated if more.
+const COLORS: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7",
+];
+/// Width of the labels on the left of the plot area.
+const LABEL_WIDTH: f64 = 240.0;
+const PLOT_WIDTH: f64 = 560.0;
+/// Margin on the right, so the last tick label is not cut.
+const RIGHT_MARGIN: f64 = 40.0;
+const ROW_HEIGHT: f64 = 32.0;
+const AXIS_HEIGHT: f64 = 28.0;
+const SCATTER_HEIGHT: f64 = 240.0;
+/// Points of the density curve of the violin plot.
+const VIOLIN_POINTS: usize = 60;
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; color: #222; }
+h1 { font-size: 1.6em; }
+h2 { font-size: 1.3em; margin-top: 2em; border-bottom: 1px solid #ccc; }
+table { border-collapse: collapse; }
+th, td { padding: 0.2em 0.8em; border-bottom: 1px solid #eee; text-align: left; }
+td.num { text-align: right; font-variant-numeric: tabular-nums; }
+figure { margin: 1em 0; }
+figcaption { font-weight: bold; margin-bottom: 0.3em; }
+svg { font-size: 12px; }
+svg text { fill: #222; }
+.warning { color: #a15c00; font-weight: bold; }
+.legend { list-style: none; padding: 0; }
+.legend li { display: inline-block; margin-right: 1.5em; }
+.legend span { display: inline-block; width: 0.8em; height: 0.8em; margin-right: 0.3em; }
+";
+
+/// Writes the HTML report of all commands when all finished.
+pub struct HtmlReporter<W: std::io::Write> {
+    writer: W,
+}
+
+impl HtmlReporter<std::io::BufWriter<std::fs::File>> {
+    pub fn create(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(Self::new(crate::reporter::create_file(path)?))
+    }
+}
+
+impl<W: std::io::Write> HtmlReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: std::io::Write> Reporter for HtmlReporter<W> {
+    fn all_finished(&mut self, result: &BenchmarkResult) -> anyhow::Result<()> {
+        self.writer.write_all(to_html(result).as_bytes())?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Samples of one command and backend to chart.
+pub(crate) struct Series {
+    /// Such as "#1 name (backend) (partial)".
+    pub label: String,
+    pub color: &'static str,
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+/// Series of the item for each command and backend, except those without the samples.
+///
+/// The color is by the command, so the backends of the same command have the same color.
+pub(crate) fn series(result: &BenchmarkResult, item: &MeasItem) -> Vec<Series> {
+    let mut series = Vec::new();
+    for (index, command) in result.commands.iter().enumerate() {
+        let by_backend = command.by_backend();
+        let multiple = 1 < by_backend.len();
+        for command in by_backend {
+            let samples = command.samples(item);
+            if samples.is_empty() {
+                continue;
+            }
+            series.push(Series {
+                label: label(index, &command, multiple),
+                color: COLORS[index % COLORS.len()],
+                stats: Stats::new(&samples),
+                samples,
+            });
+        }
+    }
+    series
+}
+
+/// Label of the index-th command split by the backend, with the backend name if multiple.
+pub(crate) fn label(index: usize, command: &CommandResult, multiple: bool) -> String {
+    let mut label = format!("#{} {}", index + 1, command.name);
+    if multiple {
+        let _ = write!(label, " ({})", command.backends[0]);
+    }
+    if command.partial {
+        label.push_str(" (partial)");
+    }
+    label
+}
+
+/// The whole HTML document of the result.
+fn to_html(result: &BenchmarkResult) -> String {
+    let loops = result.commands.first().map(|x| x.loops).unwrap_or(1);
+    let mut html = String::new();
+    let _ = write!(
+        html,
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+        <title>mntime report</title>\n<style>\n{}</style>\n</head>\n<body>\n<h1>mntime report</h1>\n",
+        STYLE
+    );
+    if result.interrupted {
+        html.push_str("<p class=\"warning\">Interrupted, so the remaining commands are not run and the results are incomplete.</p>\n");
+    }
+
+    if let Some(metadata) = &result.metadata {
+        html.push_str("<h2>Metadata</h2>\n<table>\n");
+        let version = format!("mntime {} at {}", metadata.version, metadata.timestamp);
+        for (label, value) in std::iter::once(("Version", version)).chain(metadata.lines()) {
+            let _ = writeln!(
+                html,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                label,
+                escape(&value)
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str(
+        "<h2>Summary</h2>\n<table>\n<tr><th>#</th><th>Name</th><th>Command</th><th>Runs</th>",
+    );
+    for item in &CHART_ITEMS {
+        let _ = write!(html, "<th>{}</th>", escape(&meas_item_name(item, loops)));
+    }
+    html.push_str("</tr>\n");
+    for (index, command) in result.commands.iter().enumerate() {
+        let _ = write!(
+            html,
+            "<tr><td class=\"num\">{}</td><td>{}{}</td><td><code>{}</code></td><td class=\"num\">{}</td>",
+            index + 1,
+            escape(&command.name),
+            if command.partial { " (partial)" } else { "" },
+            escape(&command.command),
+            command.runs.len()
+        );
+        for item in &CHART_ITEMS {
+            let stats = command.stats(item);
+            if stats.count() == 0 {
+                html.push_str("<td></td>");
+            } else {
+                let _ = write!(
+                    html,
+                    "<td class=\"num\">{} ± {}</td>",
+                    meas_item_unit_value(item, stats.mean, loops),
+                    meas_item_unit_value(item, stats.stdev, loops)
+                );
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+    html.push_str("</table>\n");
+
+    for item in &CHART_ITEMS {
+        let series = series(result, item);
+        if series.is_empty() {
+            continue;
+        }
+        let _ = writeln!(html, "<h2>{}</h2>", escape(&meas_item_name(item, loops)));
+        html.push_str(&figure("Mean ± σ", &bar_chart(item, loops, &series)));
+        html.push_str(&figure(
+            "Box plot (whiskers exclude the outliers out of LCL and UCL)",
+            &box_chart(item, loops, &series),
+        ));
+        html.push_str(&figure("Violin plot", &violin_chart(item, loops, &series)));
+        html.push_str(&figure("Each run", &scatter_chart(item, loops, &series)));
+        html.push_str("<ul class=\"legend\">\n");
+        for x in &series {
+            let _ = writeln!(
+                html,
+                "<li><span style=\"background:{}\"></span>{}</li>",
+                x.color,
+                escape(&x.label)
+            );
+        }
+        html.push_str("</ul>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn figure(caption: &str, svg: &str) -> String {
+    format!(
+        "<figure>\n<figcaption>{}</figcaption>\n{}</figure>\n",
+        escape(caption),
+        svg
+    )
+}
+
+/// Linear mapping of the values from lower to upper onto a length.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Scale {
+    lower: f64,
+    upper: f64,
+}
+
+impl Scale {
+    /// Scale of the values with 5% padding on both sides, or around the value if all are the same.
+    fn of(values: impl Iterator<Item = f64>) -> Self {
+        let (min, max) = values
+            .filter(|x| x.is_finite())
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+        if max < min {
+            return Self {
+                lower: 0.0,
+                upper: 1.0,
+            };
+        }
+        let pad = if min < max {
+            (max - min) * 0.05
+        } else if min != 0.0 {
+            min.abs() * 0.1
+        } else {
+            1.0
+        };
+        Self {
+            lower: min - pad,
+            upper: max + pad,
+        }
+    }
+
+    /// Position of the value in the length.
+    fn pos(&self, val: f64, len: f64) -> f64 {
+        (val - self.lower) / (self.upper - self.lower) * len
+    }
+
+    /// Values of 5 evenly spaced ticks.
+    fn ticks(&self) -> impl Iterator<Item = f64> + '_ {
+        (0..5).map(|i| self.lower + (self.upper - self.lower) * i as f64 / 4.0)
+    }
+}
+
+/// Open the svg element of the rows of the series, with the labels on the left.
+fn start_rows(svg: &mut String, series: &[Series], row_height: f64) -> f64 {
+    let height = series.len() as f64 * row_height + AXIS_HEIGHT;
+    let width = LABEL_WIDTH + PLOT_WIDTH + RIGHT_MARGIN;
+    let _ = writeln!(
+        svg,
+        "<svg width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" role=\"img\">",
+        w = width,
+        h = height
+    );
+    for (i, x) in series.iter().enumerate() {
+        let _ = writeln!(
+            svg,
+            "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+            LABEL_WIDTH - 8.0,
+            (i as f64 + 0.5) * row_height,
+            escape(&truncate(&x.label, 36))
+        );
+    }
+    height - AXIS_HEIGHT
+}
+
+/// Draw the horizontal axis at y, with the grid lines up to the top.
+fn x_axis(svg: &mut String, scale: &Scale, item: &MeasItem, loops: u16, y: f64) {
+    for tick in scale.ticks() {
+        let x = LABEL_WIDTH + scale.pos(tick, PLOT_WIDTH);
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{x:.1}\" y1=\"0\" x2=\"{x:.1}\" y2=\"{y:.1}\" stroke=\"#e5e5e5\"/>\n\
+            <text x=\"{x:.1}\" y=\"{ty:.1}\" text-anchor=\"middle\">{label}</text>",
+            x = x,
+            y = y,
+            ty = y + 18.0,
+            label = escape(&meas_item_unit_value(item, tick, loops))
+        );
+    }
+    let _ = writeln!(
+        svg,
+        "<line x1=\"{:.1}\" y1=\"{y:.1}\" x2=\"{:.1}\" y2=\"{y:.1}\" stroke=\"#888\"/>",
+        LABEL_WIDTH,
+        LABEL_WIDTH + PLOT_WIDTH,
+        y = y
+    );
+}
+
+/// Bars of the mean from zero, with the error bars of the standard deviation.
+fn bar_chart(item: &MeasItem, loops: u16, series: &[Series]) -> String {
+    let mut svg = String::new();
+    let scale = Scale {
+        lower: 0.0,
+        ..Scale::of(
+            series
+                .iter()
+                .flat_map(|x| [0.0, x.stats.mean + x.stats.stdev]),
+        )
+    };
+    let y = start_rows(&mut svg, series, ROW_HEIGHT);
+    x_axis(&mut svg, &scale, item, loops, y);
+    let x = |val: f64| LABEL_WIDTH + scale.pos(val, PLOT_WIDTH);
+    for (i, s) in series.iter().enumerate() {
+        let top = i as f64 * ROW_HEIGHT;
+        let center = top + ROW_HEIGHT / 2.0;
+        let (mean, stdev) = (s.stats.mean, s.stats.stdev);
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{}</title></rect>",
+            x(0.0),
+            top + ROW_HEIGHT * 0.2,
+            x(mean) - x(0.0),
+            ROW_HEIGHT * 0.6,
+            s.color,
+            escape(&format!(
+                "{}: {} ± {}",
+                s.label,
+                meas_item_unit_value(item, mean, loops),
+                meas_item_unit_value(item, stdev, loops)
+            ))
+        );
+        let (lower, upper) = (x((mean - stdev).max(0.0)), x(mean + stdev));
+        let _ = writeln!(
+            svg,
+            "<path d=\"M{lower:.1} {c:.1}H{upper:.1}M{lower:.1} {t:.1}V{b:.1}M{upper:.1} {t:.1}V{b:.1}\" stroke=\"#222\" fill=\"none\"/>",
+            lower = lower,
+            upper = upper,
+            c = center,
+            t = center - 5.0,
+            b = center + 5.0
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Box from the first to the third quartile with the median, the whiskers without the outliers,
+/// and the outliers as the points.
+fn box_chart(item: &MeasItem, loops: u16, series: &[Series]) -> String {
+    let mut svg = String::new();
+    let scale = Scale::of(series.iter().flat_map(|x| x.samples.iter().copied()));
+    let y = start_rows(&mut svg, series, ROW_HEIGHT);
+    x_axis(&mut svg, &scale, item, loops, y);
+    let x = |val: f64| LABEL_WIDTH + scale.pos(val, PLOT_WIDTH);
+    for (i, s) in series.iter().enumerate() {
+        let top = i as f64 * ROW_HEIGHT;
+        let center = top + ROW_HEIGHT / 2.0;
+        let stats = &s.stats;
+        let (q1, q3) = (x(stats.quantile(0.25)), x(stats.quantile(0.75)));
+        let (lower, upper) = (
+            x(stats.min_excluding_outlier()),
+            x(stats.max_excluding_outlier()),
+        );
+        let _ = writeln!(
+            svg,
+            "<path d=\"M{lower:.1} {c:.1}H{q1:.1}M{q3:.1} {c:.1}H{upper:.1}M{lower:.1} {t:.1}V{b:.1}M{upper:.1} {t:.1}V{b:.1}\" stroke=\"#222\" fill=\"none\"/>",
+            lower = lower,
+            upper = upper,
+            q1 = q1,
+            q3 = q3,
+            c = center,
+            t = center - 5.0,
+            b = center + 5.0
+        );
+        let _ = writeln!(
+            svg,
+            "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\" stroke=\"#222\"/>",
+            q1,
+            top + ROW_HEIGHT * 0.2,
+            (q3 - q1).max(1.0),
+            ROW_HEIGHT * 0.6,
+            s.color
+        );
+        let median = x(stats.median());
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{m:.1}\" y1=\"{:.1}\" x2=\"{m:.1}\" y2=\"{:.1}\" stroke=\"#222\" stroke-width=\"2\"><title>{}</title></line>",
+            top + ROW_HEIGHT * 0.2,
+            top + ROW_HEIGHT * 0.8,
+            escape(&format!("median {}", meas_item_unit_value(item, stats.median(), loops))),
+            m = median
+        );
+        for &val in s.samples.iter().filter(|x| stats.is_outlier(**x)) {
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"none\" stroke=\"{}\"><title>{}</title></circle>",
+                x(val),
+                center,
+                s.color,
+                escape(&meas_item_unit_value(item, val, loops))
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Mirrored density of the samples, with the median as the white line.
+fn violin_chart(item: &MeasItem, loops: u16, series: &[Series]) -> String {
+    let mut svg = String::new();
+    let row_height = ROW_HEIGHT * 1.5;
+    let scale = Scale::of(series.iter().flat_map(|x| x.samples.iter().copied()));
+    let y = start_rows(&mut svg, series, row_height);
+    x_axis(&mut svg, &scale, item, loops, y);
+    let x = |val: f64| LABEL_WIDTH + scale.pos(val, PLOT_WIDTH);
+    for (i, s) in series.iter().enumerate() {
+        let center = (i as f64 + 0.5) * row_height;
+        let half = row_height * 0.45;
+        let curve = density(&s.samples, VIOLIN_POINTS);
+        if curve.len() < 2 {
+            // All the same, so a line at the value.
+            let _ = writeln!(
+                svg,
+                "<line x1=\"{v:.1}\" y1=\"{:.1}\" x2=\"{v:.1}\" y2=\"{:.1}\" stroke=\"{}\" stroke-width=\"3\"/>",
+                center - half,
+                center + half,
+                s.color,
+                v = x(s.stats.median())
+            );
+            continue;
+        }
+        let max = curve.iter().map(|x| x.1).fold(0.0, f64::max);
+        let mut d = String::new();
+        for (n, (val, density)) in curve.iter().enumerate() {
+            let _ = write!(
+                d,
+                "{}{:.1} {:.1}",
+                if n == 0 { "M" } else { "L" },
+                x(*val),
+                center - density / max * half
+            );
+        }
+        for (val, density) in curve.iter().rev() {
+            let _ = write!(d, "L{:.1} {:.1}", x(*val), center + density / max * half);
+        }
+        let _ = writeln!(
+            svg,
+            "<path d=\"{}Z\" fill=\"{}\" fill-opacity=\"0.8\" stroke=\"{}\"/>",
+            d, s.color, s.color
+        );
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{m:.1}\" y1=\"{:.1}\" x2=\"{m:.1}\" y2=\"{:.1}\" stroke=\"#fff\" stroke-width=\"2\"/>",
+            center - half * 0.5,
+            center + half * 0.5,
+            m = x(s.stats.median())
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Samples in order of the runs, to see the drift and the warmup.
+fn scatter_chart(item: &MeasItem, loops: u16, series: &[Series]) -> String {
+    let mut svg = String::new();
+    let width = LABEL_WIDTH + PLOT_WIDTH + RIGHT_MARGIN;
+    let height = SCATTER_HEIGHT + AXIS_HEIGHT;
+    let _ = writeln!(
+        svg,
+        "<svg width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\" role=\"img\">",
+        w = width,
+        h = height
+    );
+    let scale = Scale::of(series.iter().flat_map(|x| x.samples.iter().copied()));
+    let y = |val: f64| SCATTER_HEIGHT - scale.pos(val, SCATTER_HEIGHT);
+    for tick in scale.ticks() {
+        let _ = writeln!(
+            svg,
+            "<line x1=\"{:.1}\" y1=\"{y:.1}\" x2=\"{:.1}\" y2=\"{y:.1}\" stroke=\"#e5e5e5\"/>\n\
+            <text x=\"{:.1}\" y=\"{y:.1}\" text-anchor=\"end\" dominant-baseline=\"middle\">{}</text>",
+            LABEL_WIDTH,
+            LABEL_WIDTH + PLOT_WIDTH,
+            LABEL_WIDTH - 8.0,
+            escape(&meas_item_unit_value(item, tick, loops)),
+            y = y(tick)
+        );
+    }
+    let runs = series.iter().map(|x| x.samples.len()).max().unwrap_or(1);
+    // Runs are 1-based and centered in each slot.
+    let x = |n: usize| LABEL_WIDTH + (n as f64 - 0.5) / runs as f64 * PLOT_WIDTH;
+    let _ = writeln!(
+        svg,
+        "<line x1=\"{:.1}\" y1=\"{h:.1}\" x2=\"{:.1}\" y2=\"{h:.1}\" stroke=\"#888\"/>\n\
+        <text x=\"{:.1}\" y=\"{t:.1}\" text-anchor=\"middle\">run 1</text>\n\
+        <text x=\"{:.1}\" y=\"{t:.1}\" text-anchor=\"middle\">run {}</text>",
+        LABEL_WIDTH,
+        LABEL_WIDTH + PLOT_WIDTH,
+        x(1),
+        x(runs),
+        runs,
+        h = SCATTER_HEIGHT,
+        t = SCATTER_HEIGHT + 18.0
+    );
+    for s in series {
+        let points: Vec<_> = s
+            .samples
+            .iter()
+            .enumerate()
+            .map(|(n, val)| format!("{:.1},{:.1}", x(n + 1), y(*val)))
+            .collect();
+        let _ = writeln!(
+            svg,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-opacity=\"0.4\"/>",
+            points.join(" "),
+            s.color
+        );
+        for (n, &val) in s.samples.iter().enumerate() {
+            let _ = writeln!(
+                svg,
+                "<circle cx=\"{:.1}\" cy=\"{:.1}\" r=\"3\" fill=\"{}\"><title>{}</title></circle>",
+                x(n + 1),
+                y(val),
+                s.color,
+                escape(&format!(
+                    "{} run {}: {}",
+                    s.label,
+                    n + 1,
+                    meas_item_unit_value(item, val, loops)
+                ))
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Gaussian kernel density of the samples at the points evenly spaced over 3 bandwidths beyond them.
+///
+/// The bandwidth is by Silverman's rule of thumb. Empty if the samples do not vary.
+fn density(samples: &[f64], points: usize) -> Vec<(f64, f64)> {
+    let stats = Stats::new(samples);
+    let count = stats.count() as f64;
+    let bandwidth = 1.06 * stats.stdev * count.powf(-0.2);
+    if bandwidth.is_nan() || bandwidth <= 0.0 || points < 2 {
+        return Vec::new();
+    }
+    let lower = stats.min() - bandwidth * 3.0;
+    let upper = stats.max() + bandwidth * 3.0;
+    (0..points)
+        .map(|i| {
+            let at = lower + (upper - lower) * i as f64 / (points - 1) as f64;
+            let sum: f64 = samples
+                .iter()
+                .filter(|x| x.is_finite())
+                .map(|x| (-0.5 * ((at - x) / bandwidth).powi(2)).exp())
+                .sum();
+            (
+                at,
+                sum / (count * bandwidth * (2.0 * std::f64::consts::PI).sqrt()),
+            )
+        })
+        .collect()
+}
+
+/// Escape the text for HTML, including in the attributes.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Shorten the label to fit on the left of the plot, with the full one in the legend.
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut truncated: String = text.chars().take(max - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn command(name: &str, samples: &[(f64, f64)]) -> CommandResult {
+        CommandResult {
+            name: String::from(name),
+            command: String::from(name),
+            loops: 1,
+            runs: samples
+                .iter()
+                .map(|(real, rss)| {
+                    HashMap::from([(MeasItem::Real, *real), (MeasItem::MaxResident, *rss)])
+                })
+                .collect(),
+            backends: vec![String::from("builtin"); samples.len()],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn html_report() {
+        let result = BenchmarkResult {
+            commands: vec![
+                command(
+                    "sleep 1 && echo <done>",
+                    &[
+                        (3.0, 1024.0),
+                        (2.9, 2048.0),
+                        (3.1, 1024.0),
+                        (2.95, 1024.0),
+                        (3.05, 1024.0),
+                        (3.0, 1024.0),
+                        (10.0, 1024.0),
+                    ],
+                ),
+                CommandResult {
+                    partial: true,
+                    ..command("true", &[(0.5, 512.0), (0.5, 512.0)])
+                },
+            ],
+            interrupted: true,
+            ..Default::default()
+        };
+        let html = to_html(&result);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.ends_with("</html>\n"));
+        assert!(html.contains("sleep 1 &amp;&amp; echo &lt;done&gt;"));
+        assert!(!html.contains("<done>"));
+        assert!(html.contains("#2 true (partial)"));
+        assert!(html.contains("class=\"warning\""));
+        // 4 charts for each of the time and the memory.
+        assert_eq!(html.matches("<svg ").count(), 8);
+        assert_eq!(html.matches("<figure>").count(), 8);
+        // Each run in the scatter plots, and the outliers in the box plots.
+        let outliers: usize = result
+            .commands
+            .iter()
+            .flat_map(|x| {
+                CHART_ITEMS
+                    .iter()
+                    .map(move |item| x.stats(item).outlier_count)
+            })
+            .sum();
+        assert!(0 < outliers);
+        assert_eq!(html.matches("<circle cx").count(), 2 * 9 + outliers);
+        // No external assets.
+        assert!(!html.contains("src="));
+        assert!(!html.contains("href="));
+
+        let html = to_html(&BenchmarkResult::default());
+        assert!(!html.contains("<svg "));
+    }
+
+    #[test]
+    fn html_density() {
+        let curve = density(&[1.0, 2.0, 3.0], 11);
+        assert_eq!(curve.len(), 11);
+        // Symmetric around the mean.
+        assert_eq!(curve[5].0, 2.0);
+        assert!((curve[0].1 - curve[10].1).abs() < 1e-12);
+        assert!(curve[0].1 < curve[5].1);
+        assert!(density(&[2.0, 2.0], 11).is_empty());
+    }
+
+    #[test]
+    fn html_scale() {
+        let scale = Scale::of([1.0, 3.0].into_iter());
+        assert_eq!(scale.lower, 0.9);
+        assert_eq!(scale.upper, 3.1);
+        assert_eq!(scale.pos(2.0, 100.0), 50.0);
+        assert_eq!(scale.ticks().count(), 5);
+        let scale = Scale::of([2.0].into_iter());
+        assert!(scale.lower < 2.0 && 2.0 < scale.upper);
+        assert_eq!(Scale::of(std::iter::empty()).upper, 1.0);
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(
+            escape("<a href='x'>&</a>"),
+            "&lt;a href=&#39;x&#39;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright © ArkBig
+//! This file provides the JSON compatible with hyperfine, by `--export-hyperfine-json` and `mntime import`.
+//!
+//! The schema is the same as `--export-json` of hyperfine, so the tools consuming it can be used as they are,
+//! and the results of hyperfine can be compared in the reports of mntime.
+
+use std::collections::HashMap;
+
+use anyhow::Context as _;
+
+use crate::benchmark::{BenchmarkResult, CommandResult};
+use crate::cmd::MeasItem;
+use crate::reporter::Reporter;
+use crate::stats::Stats;
+
+/// Name of the backend of the imported runs.
+const IMPORTED_BACKEND: &str = "hyperfine";
+
+/// Writes the results of all commands in the schema of hyperfine when all finished.
+///
+/// The times are per one loop, because hyperfine has no loops.
+/// `user` and `system` are the means, and they are 0 if not measured, as hyperfine does not allow null.
+pub struct HyperfineReporter<W: std::io::Write> {
+    writer: W,
+}
+
+impl HyperfineReporter<std::io::BufWriter<std::fs::File>> {
+    pub fn create(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(Self::new(crate::reporter::create_file(path)?))
+    }
+}
+
+impl<W: std::io::Write> HyperfineReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: std::io::Write> Reporter for HyperfineReporter<W> {
+    fn all_finished(&mut self, result: &BenchmarkResult) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(&mut self.writer, &to_json(result))?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn to_json(result: &BenchmarkResult) -> serde_json::Value {
+    let results: Vec<_> = result
+        .commands
+        .iter()
+        .map(|command| {
+            let per_loop = |val: f64| val / command.loops.max(1) as f64;
+            let (times, exit_codes): (Vec<_>, Vec<_>) = command
+                .runs
+                .iter()
+                .filter_map(|report| {
+                    let real = report.get(&MeasItem::Real)?;
+                    let exit_code = report.get(&MeasItem::ExitStatus).map(|x| *x as i64);
+                    Some((per_loop(*real), exit_code))
+                })
+                .unzip();
+            let stats = Stats::new(&times);
+            let mean_of = |item: &MeasItem| {
+                let stats = command.stats(item);
+                if stats.count() == 0 {
+                    0.0
+                } else {
+                    per_loop(stats.mean)
+                }
+            };
+            serde_json::json!({
+                "command": command.name,
+                "mean": stats.mean,
+                // Same as hyperfine, which has no standard deviation of one run.
+                "stddev": (1 < stats.count()).then_some(stats.stdev),
+                "median": stats.median(),
+                "user": mean_of(&MeasItem::User),
+                "system": mean_of(&MeasItem::Sys),
+                "min": stats.min(),
+                "max": stats.max(),
+                "times": times,
+                "exit_codes": exit_codes,
+            })
+        })
+        .collect();
+    serde_json::json!({ "results": results })
+}
+
+/// Load the results from the file exported by `--export-json` of hyperfine.
+///
+/// Each run has the wall clock time and the exit status. The user and system times are not imported,
+/// because hyperfine has only their means.
+pub fn import(path: &std::path::Path) -> anyhow::Result<BenchmarkResult> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read the import file `{}`", path.display()))?;
+    from_json(&text).with_context(|| {
+        format!(
+            "Could not import `{}` as the JSON of hyperfine",
+            path.display()
+        )
+    })
+}
+
+fn from_json(text: &str) -> anyhow::Result<BenchmarkResult> {
+    let json: serde_json::Value = serde_json::from_str(text)?;
+    let results = json["results"]
+        .as_array()
+        .context("The `results` array is not found.")?;
+    let mut commands = Vec::new();
+    for (i, result) in results.iter().enumerate() {
+        let command = result["command"]
+            .as_str()
+            .with_context(|| format!("The `command` of results[{}] is not found.", i))?;
+        let times = result["times"]
+            .as_array()
+            .with_context(|| format!("The `times` of results[{}] is not found.", i))?;
+        let mut runs = Vec::new();
+        for (n, time) in times.iter().enumerate() {
+            let time = time
+                .as_f64()
+                .with_context(|| format!("The times[{}] of results[{}] is not a number.", n, i))?;
+            let mut report = HashMap::from([(MeasItem::Real, time)]);
+            // null if killed by a signal.
+            if let Some(code) = result["exit_codes"][n].as_f64() {
+                report.insert(MeasItem::ExitStatus, code);
+            }
+            runs.push(report);
+        }
+        commands.push(CommandResult {
+            name: command.to_string(),
+            command: command.to_string(),
+            loops: 1,
+            backends: vec![IMPORTED_BACKEND.to_string(); runs.len()],
+            runs,
+            ..Default::default()
+        });
+    }
+    Ok(BenchmarkResult {
+        commands,
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hyperfine_to_json() {
+        let result = BenchmarkResult {
+            commands: vec![CommandResult {
+                name: String::from("sleep"),
+                command: String::from("sleep 0.1"),
+                loops: 2,
+                runs: vec![
+                    HashMap::from([
+                        (MeasItem::Real, 2.0),
+                        (MeasItem::User, 0.25),
+                        (MeasItem::ExitStatus, 0.0),
+                    ]),
+                    HashMap::from([
+                        (MeasItem::Real, 4.0),
+                        (MeasItem::User, 0.75),
+                        (MeasItem::ExitStatus, 1.0),
+                    ]),
+                    // Not measured.
+                    HashMap::from([(MeasItem::ExitStatus, 0.0)]),
+                ],
+                backends: vec![String::from("gnu"); 3],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let json = to_json(&result);
+        let command = &json["results"][0];
+        assert_eq!(command["command"], "sleep");
+        assert_eq!(command["mean"], 1.5);
+        assert_eq!(command["min"], 1.0);
+        assert_eq!(command["max"], 2.0);
+        assert!(command["stddev"].is_f64());
+        assert_eq!(command["user"], 0.25);
+        assert_eq!(command["system"], 0.0);
+        assert_eq!(command["times"], serde_json::json!([1.0, 2.0]));
+        assert_eq!(command["exit_codes"], serde_json::json!([0, 1]));
+    }
+
+    #[test]
+    fn hyperfine_from_json() {
+        let text = r#"{
+            "results": [
+                {
+                    "command": "sleep 0.1",
+                    "mean": 0.1, "stddev": 0.001, "median": 0.1, "user": 0.0, "system": 0.0,
+                    "min": 0.099, "max": 0.101,
+                    "times": [0.099, 0.1, 0.101],
+                    "exit_codes": [0, 0, null]
+                }
+            ]
+        }"#;
+        let result = from_json(text).unwrap();
+        let command = &result.commands[0];
+        assert_eq!(command.name, "sleep 0.1");
+        assert_eq!(command.samples(&MeasItem::Real), vec![0.099, 0.1, 0.101]);
+        assert_eq!(command.samples(&MeasItem::ExitStatus), vec![0.0, 0.0]);
+        assert_eq!(command.backends, vec![IMPORTED_BACKEND; 3]);
+
+        // Round trip.
+        let json = to_json(&result);
+        assert_eq!(json["results"][0]["times"][2], 0.101);
+        assert_eq!(
+            from_json(&json.to_string()).unwrap().commands[0].runs.len(),
+            3
+        );
+
+        assert!(from_json("{}").is_err());
+        assert!(from_json(r#"{"results": [{"command": "a"}]}"#).is_err());
+    }
+}
@@ -3,10 +3,55 @@
 # mntime-lib
 
 This is main process of [mntime](https://github.com/arkbig/mntime).
+
+It can also be used to measure commands from Rust code without the terminal output.
+
+```no_run
+let result = mntime_lib::Benchmark::new()
+    .command("sleep 0.1")
+    .runs(3)
+    .run()
+    .unwrap();
+let real = result.commands[0].stats(&mntime_lib::MeasItem::Real);
+println!("{} ± {}", real.mean, real.stdev);
+```
 */
 
 pub mod app;
+mod benchmark;
+mod browser;
+mod cache;
+mod cgroup;
 mod cli_args;
 mod cmd;
+mod histogram;
+mod html;
+mod hyperfine;
+mod metadata;
+mod metric;
+mod perf;
+mod plot;
+mod proc_sampler;
+mod reporter;
+#[cfg(unix)]
+mod rusage;
+mod sched;
 mod stats;
+mod system;
 mod terminal;
+
+pub use benchmark::{Backend, Benchmark, BenchmarkCommand, BenchmarkResult, CommandResult};
+pub use cmd::{
+    meas_item_key, meas_item_name, meas_item_unit_value, ExecOptions, MeasItem, MeasureBackend,
+    ReadyStatus,
+};
+pub use html::HtmlReporter;
+pub use hyperfine::HyperfineReporter;
+pub use metadata::Metadata;
+pub use metric::{Metric, MetricUnit};
+pub use plot::{GnuplotReporter, VegaReporter};
+pub use proc_sampler::MemorySample;
+pub use reporter::{CsvReporter, JsonReporter, Reporter};
+pub use sched::SchedPolicy;
+pub use stats::Stats;
+pub use system::SystemState;
@@ -6,6 +6,7 @@ This `mntime` command internally uses [BSD Time `time -l`][time] or [GNU Time `g
 
 [time]:https://www.freebsd.org/cgi/man.cgi?query=time
 [gtime]:https://man7.org/linux/man-pages/man1/time.1.html
+[busybox]:https://busybox.net/downloads/BusyBox.html
 
 The `time -l` option and `gtime -v` option measure memory usage as well as execution time.
 If you are only measuring execution time, you can use shell built-in `time` instead.
@@ -44,7 +45,10 @@ MSRV: `mntime` requires rustc 1.74.0 or newer.
 brew install gnu-time
 ```
 
-If neither of those is available, use bash built-in time, which only measures time and not memory-related data.
+[BusyBox][busybox] `time -v` is also supported, such as in Alpine Linux containers.
+
+If none of those is available, use the native backend on Unix, which reads the rusage of the command without any time command.
+Otherwise use the shell built-in time. bash, zsh and fish are supported, and zsh also measures memory-related data.
 
 ### mntime
 
@@ -82,6 +86,15 @@ Cycles elapsed                :    1,108,346 ± 39,878 (3.6 %) [1,075,217 ≦ 1,
 Peak memory footprint         :    903.1 KiB ± 7.869 KiB (0.9 %) [896.6 KiB ≦ 896.6 KiB ≦ 912.7 KiB] / 5
 ```
 
+While measuring, the progress and sparklines of the wall clock time and the maximum resident set size of each run so far are shown, so drifting or bimodal timings can be seen before the report.
+
+The following keys are available while measuring.
+
+- `p` or `Space` pauses after the current run, and resumes.
+- `s` skips to the next command. The runs so far are reported, with a warning that they are partial.
+- `+` adds a run to the current command.
+- `q` or `Ctrl-C` quits. The runs so far are discarded, unless `--report-on-interrupt` is specified to report them and the exports marked as interrupted, with the exit code 130.
+
 If the number of runs is not specified, it will run 10 times. If you want to change the number of runs, you can use the `-r`/`--runs` option.
 
 ```sh
@@ -182,11 +195,202 @@ mntime command1 --flag arg -- command2 -- 'command3 -f -- args'
 
 "--" delimiters are recommended, except when they contain " -- ".
 
+#### Labels
+
+Long commands make the report hard to read, so each command can be labeled with `-n`/`--name` in order.
+
+```sh
+mntime --name old --name new 'command1 --many --flags' 'command2 --many --flags'
+```
+
+#### Backends
+
+By default, the first available one of BSD time, GNU time, native and built-in time is used.
+Use `--backend` to choose one explicitly.
+
+```sh
+mntime --backend gnu command1
+```
+
+`--backend all-alternating` runs all available ones alternately.
+Their items have different semantics, so the statistics are reported separately for each backend.
+
+#### Metrics
+
+Numbers printed by the command itself can be aggregated in the same way with `--metric NAME[:UNIT]=REGEX`.
+The first capture group is the value, and UNIT is one of `duration`, `bytes` or `count` (default).
+NAME must differ from the keys of the built-in items, such as `Real`, so that they are not mixed up in the exports.
+
+```sh
+mntime --metric 'parse:duration=parse: (\d+ms)' --metric 'rate=items/s: ([\d.]+)' command1
+```
+
+#### Memory sampling
+
+On Linux, `--sample-memory` samples RSS, PSS, swap, threads and open files of the process tree from /proc while running.
+This shows whether memory spikes briefly or stays high, with the mean RSS and the time to peak RSS.
+
+```sh
+mntime --sample-memory command1
+```
+
+#### Process tree
+
+Time commands report the largest single process as "Maximum resident set size".
+For commands that spawn many processes, such as builds, `--process-tree` accounts all descendant processes on Linux.
+It reports the peak RSS of the combined tree, and the number of processes, CPU time and peak RSS for each executable name.
+The processes are sampled every tick, so the CPU time of the short-lived ones exited between the samples is reported as "[others] CPU time".
+With `--cgroup`, the processes in the cgroup of each run are sampled, which includes the orphans such as daemons from the start.
+
+```sh
+mntime --process-tree 'make -j8'
+```
+
+#### Process settings
+
+For low-noise measurements, `--cpu-affinity`, `--nice` and `--sched` run the commands on the specified CPUs, such as isolated ones, with the nice value and the scheduling policy.
+Unlike wrapping with `taskset` or `nice`, they are not a part of the measured command, and the applied settings are shown in the metadata before the reports.
+
+```sh
+mntime --cpu-affinity 2-3 --nice -5 --sched fifo 'command1'
+```
+
+#### Cold cache
+
+Usually only the first run is cold, and the rest are warm because of the page cache.
+On Linux, `--cold-cache` evicts the page cache of the specified files or directories before each run, without any privileges.
+The ones that cannot be evicted, such as missing ones, are warned and skipped.
+`--drop-caches` drops the page cache of the whole system instead, which needs root.
+
+```sh
+mntime --cold-cache ./target/release/app --cold-cache ./assets ./target/release/app
+```
+
+#### I/O
+
+Block inputs and outputs of the time commands are almost always zero when the files are in the page cache.
+On Linux, `--proc-io` reads `/proc/pid/io` and reports the bytes and syscalls of read and write, and the bytes actually read from and written to storage.
+With `--process-tree`, the I/O of orphaned processes is added.
+
+```sh
+mntime --proc-io 'grep -r TODO src'
+```
+
+#### Performance counters
+
+On Linux, `--perf-counters` counts instructions, cycles, branch misses, cache misses and task clock of the whole process tree in user space by `perf_event_open`.
+Instructions are much less noisy than the elapsed time, so they are suitable for regression checks on CI.
+Counters that are not permitted by `/proc/sys/kernel/perf_event_paranoid` or not supported, such as in VMs without PMU, are skipped with a warning.
+
+```sh
+mntime --perf-counters --backend native 'make -j8'
+```
+
+#### Cgroup
+
+On Linux, `--cgroup` runs each run in a transient cgroup v2 and reports the peak memory, CPU time, throttling and I/O of the whole process tree.
+`--memory-limit` and `--cpu-quota` imply it, and limit the resources like containers.
+Only the controllers enabled in `cgroup.subtree_control` of the current cgroup are available, such as in a delegated cgroup.
+While benchmarking, mntime moves itself into `mntime-<pid>/self` under the current cgroup, and enables them in `mntime-<pid>` for the cgroups of the runs.
+Without limits, it runs without the cgroup if it cannot be created.
+
+```sh
+mntime --memory-limit 512MiB --cpu-quota 1.5 'make -j8'
+```
+
+#### Histogram
+
+`--histogram` prints the histogram and box plot of the wall clock time after each report, because mean ± σ hides bimodal distributions.
+Other items can be specified by their keys in `--export-json`, such as `--histogram=Real,MaxResident`.
+
+```txt
+Elapsed (wall clock) time:
+  24.08 ms ≦ x < 29.27 ms │███▍ 1
+  29.27 ms ≦ x < 34.46 ms │ 0
+  34.46 ms ≦ x < 39.65 ms │ 0
+  39.65 ms ≦ x < 44.84 ms │ 0
+  44.84 ms ≦ x ≦ 50.03 ms │██████████████████████████████████████████████████ 15
+  •                                         ¦               ▓┃               ¦
+  LCL 42.71 ms                                                    UCL 57.29 ms
+```
+
+In the box plot, `¦` is the control limits of the outlier detection (LCL and UCL), `├─` and `─┤` are the min and max excluding outliers, `▓` is from the first to the third quartile, `┃` is the median and `•` are the outliers.
+
+#### Metadata
+
+The host (hostname, kernel, CPU model, core count, total memory), the version of mntime, the backends with their version strings, the shell, runs and loops, and the timestamp are shown before the benchmarks as `Metadata>`.
+The process settings, the cgroup limits and the page cache settings are shown as `Settings` if any are set.
+They are also written by `--export-json` and `--export-csv`, so results from different machines can be told apart.
+
+#### System check
+
+Before and after the runs of each command, the load average, the pressure (PSI) of CPU, memory and I/O, the CPU frequency governor and turbo boost are checked, and it is warned if they may disturb the measurement.
+They are also written by `--export-json`, so noisy results can be explained afterwards.
+`--no-system-check` turns off the warnings.
+
+### Export
+
+The results can also be written to files for integration with other tools, while showing them in the terminal.
+
+```sh
+mntime --export-json result.json --export-csv result.csv sleep 1
+```
+
+JSON has the statistics and samples of each command with the time series of `--sample-memory`.
+JSON also has the `metadata` object.
+CSV has one `command_index,name,backend,run,item,value` row per sample, preceded by the metadata as `# key: value` comment lines.
+A command stopped before all runs, such as skipped, is followed by a `# partial: command_index,name` line.
+
+`--export-html` writes a single HTML file with the charts of the wall clock time and the maximum resident set size, which can be attached to release notes and issues.
+It has the bar chart of the means, box plots, violin plots and the samples of each run for comparison, drawn in inline SVG without any external assets.
+
+```sh
+mntime --export-html report.html 'sleep 0.1' 'sleep 0.2'
+```
+
+To render the charts by the standard tools offline, `--export-gnuplot` writes `ITEM.dat` and `ITEM.gp` of each item to the directory, and `--export-vega` writes a Vega-Lite spec embedding the samples.
+The values are in seconds for the times and bytes for the sizes, same as JSON.
+
+```sh
+mntime --export-gnuplot plots --export-vega spec.vl.json 'sleep 0.1' 'sleep 0.2'
+(cd plots && gnuplot Real.gp) # plots/Real.svg, or Real.png by gnuplot -e "format='pngcairo'" Real.gp
+vl2svg spec.vl.json > spec.svg
+```
+
+#### hyperfine
+
+`--export-hyperfine-json` writes the results in the same schema as `--export-json` of [hyperfine](https://github.com/sharkdp/hyperfine), so the tools consuming it can be used as they are.
+The times are per one loop, and `user` and `system` are the means.
+
+Conversely, `mntime import` reports the results exported by hyperfine in the same way as the benchmarked ones, for comparison.
+The wall clock time and the exit status of each run are imported. The options are specified before `import`.
+
+```sh
+mntime --export-hyperfine-json mntime.json 'sleep 0.1' 'sleep 0.2'
+hyperfine --export-json hyperfine.json 'sleep 0.1' 'sleep 0.2'
+mntime --histogram --export-html hyperfine.html import hyperfine.json
+```
+
+To benchmark a command named `import`, specify it after `--`, such as `mntime -- import -version`.
+
+### Browse
+
+`--tui` opens a full-screen table of all commands and items after the benchmarks, which is easier to compare than the inline reports when there are many commands.
+
+```sh
+mntime --tui 'sleep 0.1' 'sleep 0.2' 'sleep 0.3'
+```
+
+- `↑`/`↓` (or `j`/`k`), `PageUp`/`PageDown` and `Home`/`End` select a row.
+- `s` sorts by the command, the item (to compare the commands for each item), the mean or the coefficient of variation. `r` reverses it.
+- `Enter` shows the samples of each run, the outliers in yellow and the histogram of the selected item. `Esc` goes back.
+- `q` quits, and the inline reports remain.
+
 ## Roadmap
 
-- [ ] Output in csv and json for integration with other tools.
+- [x] Output in csv and json for integration with other tools.
 - [ ] Displays the advantage between each command when measuring multiple commands.
-- [ ] Visualization of results.
+- [x] Visualization of results.
 
 See the open issues for a full list of proposed features (and known issues).
 
@@ -200,7 +404,7 @@ Direct dependencies crates:
 
 |License|crate|
 |-|-|
-|Apache-2.0 OR MIT (7)| anyhow, clap, num-format, once_cell, proc-exit, regex, thiserror|
+|Apache-2.0 OR MIT (9)| anyhow, clap, libc, num-format, once_cell, proc-exit, regex, serde_json, thiserror|
 |MIT (3)| crossterm, ratatui, strum|
 |Zlib (2)| mntime, throbber-widgets-tui|
 
@@ -212,7 +416,7 @@ Chain dependencies crates:
 |Apache-2.0 OR Apache-2.0 WITH LLVM-exception OR MIT (1)| wasi|
 |Apache-2.0 OR BSD-2-Clause OR MIT (2)| zerocopy, zerocopy-derive|
 |Apache-2.0 OR BSL-1.0 (1)| ryu|
-|Apache-2.0 OR MIT (78)| ahash, allocator-api2, anstream, anstyle, anstyle-parse, anstyle-query, anstyle-wincon, anyhow, arrayvec, bitflags, bitflags, cassowary, cfg-if, clap, clap_builder, clap_derive, clap_lex, colorchoice, either, getrandom, hashbrown, heck, indoc, itertools, itoa, libc, lock_api, log, num-format, once_cell, parking_lot, parking_lot_core, paste, ppv-lite86, proc-exit, proc-macro2, quote, rand, rand_chacha, rand_core, regex, regex-automata, regex-syntax, rustversion, scopeguard, signal-hook, signal-hook-mio, signal-hook-registry, smallvec, static_assertions, syn, syn, thiserror, thiserror-impl, unicode-segmentation, unicode-width, utf8parse, winapi, winapi-i686-pc-windows-gnu, winapi-x86_64-pc-windows-gnu, windows-sys, windows-sys, windows-targets, windows-targets, windows_aarch64_gnullvm, windows_aarch64_gnullvm, windows_aarch64_msvc, windows_aarch64_msvc, windows_i686_gnu, windows_i686_gnu, windows_i686_msvc, windows_i686_msvc, windows_x86_64_gnu, windows_x86_64_gnu, windows_x86_64_gnullvm, windows_x86_64_gnullvm, windows_x86_64_msvc, windows_x86_64_msvc|
+|Apache-2.0 OR MIT (80)| ahash, allocator-api2, anstream, anstyle, anstyle-parse, anstyle-query, anstyle-wincon, anyhow, arrayvec, bitflags, bitflags, cassowary, cfg-if, clap, clap_builder, clap_derive, clap_lex, colorchoice, either, getrandom, hashbrown, heck, indoc, itertools, itoa, libc, lock_api, log, num-format, once_cell, parking_lot, parking_lot_core, paste, ppv-lite86, proc-exit, proc-macro2, quote, rand, rand_chacha, rand_core, regex, regex-automata, regex-syntax, rustversion, scopeguard, serde, serde_json, signal-hook, signal-hook-mio, signal-hook-registry, smallvec, static_assertions, syn, syn, thiserror, thiserror-impl, unicode-segmentation, unicode-width, utf8parse, winapi, winapi-i686-pc-windows-gnu, winapi-x86_64-pc-windows-gnu, windows-sys, windows-sys, windows-targets, windows-targets, windows_aarch64_gnullvm, windows_aarch64_gnullvm, windows_aarch64_msvc, windows_aarch64_msvc, windows_i686_gnu, windows_i686_gnu, windows_i686_msvc, windows_i686_msvc, windows_x86_64_gnu, windows_x86_64_gnu, windows_x86_64_gnullvm, windows_x86_64_gnullvm, windows_x86_64_msvc, windows_x86_64_msvc|
 |MIT (12)| castaway, compact_str, crossterm, crossterm_winapi, lru, mio, ratatui, redox_syscall, stability, strsim, strum, strum_macros|
 |MIT OR Unlicense (2)| aho-corasick, memchr|
 |Zlib (2)| mntime, throbber-widgets-tui|
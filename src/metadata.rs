@@ -0,0 +1,325 @@
+// Copyright © ArkBig
+//! This file provides the metadata of the host and the tools, to tell where and how the results were measured.
+
+use crate::benchmark::Benchmark;
+use crate::cmd::MeasureBackend;
+
+/// Host, tools and settings of one benchmark. Unknown values are None.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Metadata {
+    /// Version of mntime.
+    pub version: String,
+    /// UTC time when the benchmark started, such as "2024-01-02T03:04:05Z".
+    pub timestamp: String,
+    pub hostname: Option<String>,
+    /// OS name and kernel release, such as "Linux 6.8.0".
+    pub kernel: String,
+    pub cpu_model: Option<String>,
+    /// Logical CPUs available to this process.
+    pub cores: usize,
+    /// Total physical memory in bytes.
+    pub total_memory: Option<f64>,
+    /// Name and version string of each backend used.
+    pub backends: Vec<(String, Option<String>)>,
+    /// Shell and its argument to execute the commands, such as "sh -c".
+    pub shell: String,
+    pub runs: u16,
+    pub loops: u16,
+    /// CPUs the commands are pinned to. Empty if not set.
+    pub cpu_affinity: Vec<usize>,
+    pub nice: Option<i32>,
+    /// Scheduling policy in the format of `--sched`, such as "batch".
+    pub sched: Option<String>,
+    /// Memory limit of the cgroup of each run in bytes.
+    pub memory_limit: Option<u64>,
+    /// CPU quota of the cgroup of each run in CPUs, such as 1.5.
+    pub cpu_quota: Option<f64>,
+    /// Files and directories whose page cache is evicted before each run.
+    pub cold_cache: Vec<std::path::PathBuf>,
+    /// True if the page cache of the whole system is dropped before each run.
+    pub drop_caches: bool,
+}
+
+impl Metadata {
+    /// Capture the metadata of the benchmark with the backends.
+    ///
+    /// The version of each backend is taken by executing it, such as with `--version`.
+    pub fn capture(benchmark: &Benchmark, backends: &[Box<dyn MeasureBackend>]) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: format_utc(std::time::SystemTime::now()),
+            hostname: hostname(),
+            kernel: kernel(),
+            cpu_model: cpu_model(),
+            cores: std::thread::available_parallelism()
+                .map(|x| x.get())
+                .unwrap_or(1),
+            total_memory: total_memory(),
+            backends: backends
+                .iter()
+                .map(|x| (x.name().to_string(), x.version()))
+                .collect(),
+            shell: format!("{} {}", benchmark.shell, benchmark.shell_arg),
+            runs: benchmark.runs,
+            loops: benchmark.loops,
+            cpu_affinity: benchmark.cpu_affinity.clone(),
+            nice: benchmark.nice,
+            sched: benchmark.sched.and_then(|x| {
+                use clap::ValueEnum as _;
+                Some(x.to_possible_value()?.get_name().to_string())
+            }),
+            memory_limit: benchmark.memory_limit,
+            cpu_quota: benchmark.cpu_quota,
+            cold_cache: benchmark.cold_cache.clone(),
+            drop_caches: benchmark.drop_caches,
+        }
+    }
+
+    /// The settings of the processes, the cgroup and the page cache in the format of the command line options.
+    ///
+    /// Empty if none are set.
+    pub fn settings(&self) -> Vec<String> {
+        let mut settings = Vec::new();
+        if !self.cpu_affinity.is_empty() {
+            settings.push(format!(
+                "--cpu-affinity {}",
+                crate::sched::format_cpu_list(&self.cpu_affinity)
+            ));
+        }
+        if let Some(nice) = self.nice {
+            settings.push(format!("--nice {}", nice));
+        }
+        if let Some(sched) = &self.sched {
+            settings.push(format!("--sched {}", sched));
+        }
+        if let Some(bytes) = self.memory_limit {
+            settings.push(format!("--memory-limit {}", bytes));
+        }
+        if let Some(cpus) = self.cpu_quota {
+            settings.push(format!("--cpu-quota {}", cpus));
+        }
+        for path in &self.cold_cache {
+            settings.push(format!("--cold-cache {}", path.display()));
+        }
+        if self.drop_caches {
+            settings.push(String::from("--drop-caches"));
+        }
+        settings
+    }
+
+    /// Pairs of the label and the value for display, except the version and the timestamp.
+    pub fn lines(&self) -> Vec<(&'static str, String)> {
+        let unknown = || String::from("unknown");
+        let mut lines = vec![
+            (
+                "Host",
+                format!(
+                    "{} ({})",
+                    self.hostname.clone().unwrap_or_else(unknown),
+                    self.kernel
+                ),
+            ),
+            (
+                "CPU",
+                format!(
+                    "{} x {}",
+                    self.cpu_model.clone().unwrap_or_else(unknown),
+                    self.cores
+                ),
+            ),
+            (
+                "Memory",
+                self.total_memory
+                    .map(|x| {
+                        crate::cmd::meas_item_unit_value(&crate::cmd::MeasItem::MaxResident, x, 1)
+                    })
+                    .unwrap_or_else(unknown),
+            ),
+            (
+                "Backend",
+                self.backends
+                    .iter()
+                    .map(|(name, version)| match version {
+                        Some(version) => format!("{} ({})", name, version),
+                        None => name.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            ),
+            ("Shell", self.shell.clone()),
+            ("Runs", format!("{} runs x {} loops", self.runs, self.loops)),
+        ];
+        let settings = self.settings();
+        if !settings.is_empty() {
+            lines.push(("Settings", settings.join(" ")));
+        }
+        lines
+    }
+}
+
+/// Format the time as ISO 8601 in UTC.
+fn format_utc(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0);
+    // Civil from days by Howard Hinnant.
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let doe = days.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs % 86400 / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+#[cfg(unix)]
+fn hostname() -> Option<String> {
+    let mut buf = [0u8; 256];
+    // SAFETY: buf has room for the given length.
+    if unsafe { libc::gethostname(buf.as_mut_ptr().cast(), buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&x| x == 0).unwrap_or(buf.len());
+    Some(String::from_utf8_lossy(&buf[..len]).into_owned())
+}
+
+#[cfg(not(unix))]
+fn hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
+
+#[cfg(unix)]
+fn kernel() -> String {
+    // SAFETY: utsname is a plain C struct, so all zeros is a valid value.
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    // SAFETY: The pointer is valid during the call.
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return std::env::consts::OS.to_string();
+    }
+    let field = |chars: &[libc::c_char]| {
+        let bytes: Vec<u8> = chars
+            .iter()
+            .take_while(|&&x| x != 0)
+            .map(|&x| x as u8)
+            .collect();
+        String::from_utf8_lossy(&bytes).into_owned()
+    };
+    format!("{} {}", field(&uts.sysname), field(&uts.release))
+}
+
+#[cfg(not(unix))]
+fn kernel() -> String {
+    std::env::consts::OS.to_string()
+}
+
+fn cpu_model() -> Option<String> {
+    match std::fs::read_to_string("/proc/cpuinfo") {
+        Ok(cpuinfo) => parse_cpu_model(&cpuinfo),
+        Err(_) => sysctl("machdep.cpu.brand_string"),
+    }
+}
+
+fn total_memory() -> Option<f64> {
+    match std::fs::read_to_string("/proc/meminfo") {
+        Ok(meminfo) => parse_total_memory(&meminfo),
+        Err(_) => sysctl("hw.memsize")?.parse().ok(),
+    }
+}
+
+/// Value of sysctl(8), for macOS and BSD without /proc.
+fn sysctl(name: &str) -> Option<String> {
+    let output = std::process::Command::new("sysctl")
+        .args(["-n", name])
+        .stderr(std::process::Stdio::null())
+        .output()
+        .ok()
+        .filter(|x| x.status.success())?;
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string()).filter(|x| !x.is_empty())
+}
+
+/// CPU model from the content of /proc/cpuinfo. The key differs by the architecture.
+fn parse_cpu_model(cpuinfo: &str) -> Option<String> {
+    ["model name", "Hardware", "cpu model", "Model"]
+        .iter()
+        .find_map(|key| {
+            cpuinfo.lines().find_map(|line| {
+                let (k, v) = line.split_once(':')?;
+                (k.trim() == *key).then(|| v.trim().to_string())
+            })
+        })
+}
+
+/// MemTotal in bytes from the content of /proc/meminfo.
+fn parse_total_memory(meminfo: &str) -> Option<f64> {
+    let kb: f64 = meminfo
+        .lines()
+        .find_map(|line| line.strip_prefix("MemTotal:"))?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()?;
+    Some(kb * 1024.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn metadata_parse() {
+        let cpuinfo = "processor\t: 0\nvendor_id\t: GenuineIntel\nmodel name\t: Intel(R) Xeon(R) CPU @ 2.20GHz\n";
+        assert_eq!(
+            parse_cpu_model(cpuinfo),
+            Some(String::from("Intel(R) Xeon(R) CPU @ 2.20GHz"))
+        );
+        assert_eq!(parse_cpu_model("processor\t: 0\n"), None);
+        assert_eq!(
+            parse_total_memory("MemTotal:       16303428 kB\nMemFree: 1 kB\n"),
+            Some(16303428.0 * 1024.0)
+        );
+
+        let time = std::time::UNIX_EPOCH + std::time::Duration::from_secs(951_825_845);
+        assert_eq!(format_utc(time), "2000-02-29T12:04:05Z");
+        assert_eq!(format_utc(std::time::UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    }
+
+    #[test]
+    fn metadata_settings() {
+        let metadata = Metadata::default();
+        assert!(metadata.settings().is_empty());
+        assert!(!metadata.lines().iter().any(|x| x.0 == "Settings"));
+
+        let metadata = Metadata {
+            cpu_affinity: vec![0, 1, 3],
+            nice: Some(3),
+            sched: Some(String::from("batch")),
+            memory_limit: Some(512 * 1024 * 1024),
+            cpu_quota: Some(1.5),
+            cold_cache: vec![std::path::PathBuf::from("/data")],
+            drop_caches: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            metadata.lines().last().unwrap(),
+            &(
+                "Settings",
+                String::from(
+                    "--cpu-affinity 0-1,3 --nice 3 --sched batch --memory-limit 536870912 \
+                     --cpu-quota 1.5 --cold-cache /data --drop-caches"
+                )
+            )
+        );
+    }
+}
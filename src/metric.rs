@@ -0,0 +1,145 @@
+// Copyright © ArkBig
+//! This file provides user-defined metrics extracted from the output of the benchmarked command.
+
+use strum::IntoEnumIterator as _;
+
+use crate::cmd::{meas_item_key, MeasItem};
+
+/// Unit of the value of a metric, which decides how it is parsed and displayed.
+#[derive(Debug, Default, Hash, Copy, Clone, Eq, PartialEq, clap::ValueEnum)]
+pub enum MetricUnit {
+    /// Seconds. A suffix of ns, us, µs, ms, s, m or h is also accepted, such as `123ms`.
+    Duration,
+    /// Bytes. A suffix of B, K(iB), M(iB), G(iB) or T(iB) is also accepted, such as `12MiB`.
+    Bytes,
+    /// Plain number.
+    #[default]
+    Count,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum MetricError {
+    #[error("Invalid regex of the metric `{0}`: {1}")]
+    InvalidRegex(String, regex::Error),
+    #[error(
+        "The name of the metric `{0}` is the key of a built-in item, such as in `--export-json`."
+    )]
+    ReservedName(String),
+}
+
+/// Numeric value extracted from each run's stdout and stderr.
+///
+/// The first capture group of the regex is the value, or the whole match if there is no group.
+/// If it matches more than once, such as with `--loops`, the mean is used.
+#[derive(Debug, Clone)]
+pub struct Metric {
+    pub name: String,
+    pub unit: MetricUnit,
+    pub regex: regex::Regex,
+}
+
+impl PartialEq for Metric {
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+            && self.unit == other.unit
+            && self.regex.as_str() == other.regex.as_str()
+    }
+}
+
+impl Metric {
+    pub fn new(name: impl Into<String>, unit: MetricUnit, regex: &str) -> anyhow::Result<Self> {
+        let name = name.into();
+        // Otherwise it would be mixed up with the built-in item in the exports.
+        if MeasItem::iter().any(|item| meas_item_key(&item) == name) {
+            anyhow::bail!(MetricError::ReservedName(name));
+        }
+        let regex =
+            regex::Regex::new(regex).map_err(|err| MetricError::InvalidRegex(name.clone(), err))?;
+        Ok(Self { name, unit, regex })
+    }
+
+    /// Extract the value from the output. None if it does not match.
+    pub fn extract(&self, output: &str) -> Option<f64> {
+        let values: Vec<_> = self
+            .regex
+            .captures_iter(output)
+            .filter_map(|cap| {
+                let text = cap.get(1).or_else(|| cap.get(0))?.as_str();
+                parse_value(self.unit, text)
+            })
+            .collect();
+        if values.is_empty() {
+            None
+        } else {
+            Some(values.iter().sum::<f64>() / values.len() as f64)
+        }
+    }
+}
+
+/// Parse the number with an optional suffix into seconds, bytes or as is.
+pub(crate) fn parse_value(unit: MetricUnit, text: &str) -> Option<f64> {
+    let text = text.trim().replace(',', "");
+    let end = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(text.len());
+    let val: f64 = text[..end].parse().ok()?;
+    let suffix = text[end..].trim();
+    let scale = match unit {
+        MetricUnit::Duration => match suffix {
+            "ns" => 0.000_000_001,
+            "us" | "µs" => 0.000_001,
+            "ms" => 0.001,
+            "" | "s" | "sec" => 1.0,
+            "m" | "min" => 60.0,
+            "h" => 60.0 * 60.0,
+            _ => return None,
+        },
+        MetricUnit::Bytes => {
+            const KB: f64 = 1024.0;
+            match suffix.trim_end_matches("iB").trim_end_matches('B') {
+                "" => 1.0,
+                "K" | "k" => KB,
+                "M" => KB * KB,
+                "G" => KB * KB * KB,
+                "T" => KB * KB * KB * KB,
+                _ => return None,
+            }
+        }
+        MetricUnit::Count => match suffix {
+            "" => 1.0,
+            _ => return None,
+        },
+    };
+    Some(val * scale)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn metric_extract() {
+        let output = "parse: 123ms\nitems/s: 4,567\nparse: 0.877s\nheap: 12MiB\n";
+
+        let metric = Metric::new("parse", MetricUnit::Duration, r"parse: (\S+)").unwrap();
+        assert_eq!(metric.extract(output), Some(0.5));
+
+        let metric = Metric::new("rate", MetricUnit::Count, r"items/s: ([\d,]+)").unwrap();
+        assert_eq!(metric.extract(output), Some(4567.0));
+
+        let metric = Metric::new("heap", MetricUnit::Bytes, r"heap: \S+").unwrap();
+        assert_eq!(metric.extract(output), None);
+        let metric = Metric::new("heap", MetricUnit::Bytes, r"heap: (\S+)").unwrap();
+        assert_eq!(metric.extract(output), Some(12.0 * 1024.0 * 1024.0));
+
+        let metric = Metric::new("none", MetricUnit::Count, r"none: (\d+)").unwrap();
+        assert_eq!(metric.extract(output), None);
+    }
+
+    #[test]
+    fn metric_reserved_name() {
+        assert!(Metric::new("Real", MetricUnit::Duration, r"(\d+)").is_err());
+        assert!(Metric::new("MaxResident", MetricUnit::Bytes, r"(\d+)").is_err());
+        assert!(Metric::new("real", MetricUnit::Duration, r"(\d+)").is_ok());
+    }
+}
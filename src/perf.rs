@@ -0,0 +1,291 @@
+// Copyright © ArkBig
+//! This file provides the hardware performance counters of [perf_event_open(2)][perf] on Linux.
+//!
+//! The counters are opened for the benchmarked process between its fork and exec,
+//! so that they start counting at exec and are inherited by all its descendants.
+//! Only user space is counted, because the kernel is not permitted by the default `perf_event_paranoid`.
+//!
+//! [perf]:https://man7.org/linux/man-pages/man2/perf_event_open.2.html
+
+use std::collections::HashMap;
+
+use crate::cmd::MeasItem;
+
+#[derive(thiserror::Error, Debug)]
+enum PerfError {
+    #[cfg(not(target_os = "linux"))]
+    #[error("Hardware performance counters are available only on Linux.")]
+    Unsupported,
+    #[cfg(target_os = "linux")]
+    #[error("Could not open the performance counter of {0}: {1}")]
+    Open(String, std::io::Error),
+}
+
+/// Counters opened for each process by attach(), until taken by take_counters() or closed by Scope.
+static OPENED: once_cell::sync::Lazy<std::sync::Mutex<HashMap<u32, Counters>>> =
+    once_cell::sync::Lazy::new(Default::default);
+
+/// Opened counters of one process tree.
+#[derive(Debug, Default)]
+pub struct Counters {
+    counters: Vec<(MeasItem, std::fs::File)>,
+}
+
+impl Counters {
+    /// Read the counts so far. Items that could not be counted at all are skipped.
+    ///
+    /// If the counters are multiplexed because of too few hardware counters, the counts are scaled.
+    pub fn meas_items(&self) -> HashMap<MeasItem, f64> {
+        use std::io::Read as _;
+        let mut items = HashMap::new();
+        for (item, file) in &self.counters {
+            let mut file = file;
+            // value, time_enabled and time_running by PERF_FORMAT_TOTAL_TIME_ENABLED|RUNNING.
+            let mut buf = [0u8; 24];
+            if file.read_exact(&mut buf).is_err() {
+                continue;
+            }
+            let [value, enabled, running] =
+                [0, 8, 16].map(|i| u64::from_ne_bytes(buf[i..i + 8].try_into().unwrap()) as f64);
+            if running == 0.0 {
+                continue;
+            }
+            let val = value * enabled / running;
+            let val = if *item == MeasItem::TaskClock {
+                // nanoseconds
+                val / 1_000_000_000.0
+            } else {
+                val
+            };
+            items.insert(item.clone(), val);
+        }
+        items
+    }
+}
+
+/// Take the counters opened for the process.
+pub fn take_counters(pid: u32) -> Option<Counters> {
+    OPENED.lock().unwrap().remove(&pid)
+}
+
+/// Counters not taken while this is alive are closed when dropped,
+/// such as when spawning fails after they are opened, or the run ends with an error.
+///
+/// Only one process is measured at a time, so all of them are closed.
+pub struct Scope(());
+
+impl Scope {
+    pub fn enter() -> Self {
+        Self(())
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        OPENED.lock().unwrap().clear();
+    }
+}
+
+/// Check if the instruction counter can be opened, such as by `perf_event_paranoid` or the PMU in VMs.
+pub fn check() -> anyhow::Result<()> {
+    #[cfg(target_os = "linux")]
+    {
+        sys::open(&EVENTS[0], 0, false)
+            .map(drop)
+            .map_err(|err| PerfError::Open(crate::cmd::meas_item_name(&EVENTS[0].0, 1), err).into())
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        Err(PerfError::Unsupported.into())
+    }
+}
+
+/// Item, type and config of each counter.
+#[cfg(target_os = "linux")]
+const EVENTS: [(MeasItem, u32, u64); 5] = [
+    (
+        MeasItem::Instruction,
+        sys::TYPE_HARDWARE,
+        sys::HW_INSTRUCTIONS,
+    ),
+    (MeasItem::Cycle, sys::TYPE_HARDWARE, sys::HW_CPU_CYCLES),
+    (
+        MeasItem::BranchMiss,
+        sys::TYPE_HARDWARE,
+        sys::HW_BRANCH_MISSES,
+    ),
+    (
+        MeasItem::CacheMiss,
+        sys::TYPE_HARDWARE,
+        sys::HW_CACHE_MISSES,
+    ),
+    (MeasItem::TaskClock, sys::TYPE_SOFTWARE, sys::SW_TASK_CLOCK),
+];
+
+/// Open the counters for the process between fork and exec, and keep them for take_counters().
+///
+/// The child sends its pid and waits until the counters are opened by another thread,
+/// because the spawning thread is blocked until exec.
+/// Counters that cannot be opened are skipped.
+#[cfg(target_os = "linux")]
+pub fn attach(command: &mut std::process::Command) {
+    use std::os::fd::AsRawFd as _;
+    use std::os::unix::process::CommandExt as _;
+
+    let (Ok((ready_rx, ready_tx)), Ok((go_rx, go_tx))) = (sys::pipe(), sys::pipe()) else {
+        return;
+    };
+    std::thread::spawn(move || {
+        use std::io::{Read as _, Write as _};
+        let mut buf = [0u8; 4];
+        if std::fs::File::from(ready_rx).read_exact(&mut buf).is_ok() {
+            let pid = libc::pid_t::from_ne_bytes(buf);
+            let counters = Counters {
+                counters: EVENTS
+                    .iter()
+                    .filter_map(|event| Some((event.0.clone(), sys::open(event, pid, true).ok()?)))
+                    .collect(),
+            };
+            OPENED.lock().unwrap().insert(pid as u32, counters);
+            // The child has its own copy of go_tx, so it must be written instead of closed.
+            let _ = std::fs::File::from(go_tx).write_all(&[0]);
+        }
+    });
+    // SAFETY: Only async-signal-safe functions are called in the closure.
+    // The pipes are closed when the command is dropped after spawning.
+    unsafe {
+        command.pre_exec(move || {
+            let buf = libc::getpid().to_ne_bytes();
+            libc::write(ready_tx.as_raw_fd(), buf.as_ptr().cast(), buf.len());
+            let mut go = [0u8; 1];
+            libc::read(go_rx.as_raw_fd(), go.as_mut_ptr().cast(), go.len());
+            Ok(())
+        });
+    }
+}
+
+/// Minimal bindings, because libc does not have them.
+#[cfg(target_os = "linux")]
+mod sys {
+    use std::os::fd::{FromRawFd as _, OwnedFd};
+
+    pub const TYPE_HARDWARE: u32 = 0;
+    pub const TYPE_SOFTWARE: u32 = 1;
+    pub const HW_CPU_CYCLES: u64 = 0;
+    pub const HW_INSTRUCTIONS: u64 = 1;
+    pub const HW_CACHE_MISSES: u64 = 3;
+    pub const HW_BRANCH_MISSES: u64 = 5;
+    pub const SW_TASK_CLOCK: u64 = 1;
+
+    const FORMAT_TOTAL_TIME_ENABLED: u64 = 1 << 0;
+    const FORMAT_TOTAL_TIME_RUNNING: u64 = 1 << 1;
+    const FLAG_DISABLED: u64 = 1 << 0;
+    const FLAG_INHERIT: u64 = 1 << 1;
+    const FLAG_EXCLUDE_KERNEL: u64 = 1 << 5;
+    const FLAG_EXCLUDE_HV: u64 = 1 << 6;
+    const FLAG_ENABLE_ON_EXEC: u64 = 1 << 12;
+    const PERF_FLAG_FD_CLOEXEC: libc::c_ulong = 1 << 3;
+
+    /// `struct perf_event_attr` of PERF_ATTR_SIZE_VER0, which is accepted by all kernels.
+    #[repr(C)]
+    #[derive(Default)]
+    struct Attr {
+        type_: u32,
+        size: u32,
+        config: u64,
+        sample_period: u64,
+        sample_type: u64,
+        read_format: u64,
+        flags: u64,
+        wakeup_events: u32,
+        bp_type: u32,
+        config1: u64,
+    }
+
+    /// Open the counter of the process and its descendants created later.
+    ///
+    /// If enable_on_exec, it starts counting at the next exec of the process.
+    pub fn open(
+        event: &(crate::cmd::MeasItem, u32, u64),
+        pid: libc::pid_t,
+        enable_on_exec: bool,
+    ) -> std::io::Result<std::fs::File> {
+        let attr = Attr {
+            type_: event.1,
+            size: std::mem::size_of::<Attr>() as u32,
+            config: event.2,
+            read_format: FORMAT_TOTAL_TIME_ENABLED | FORMAT_TOTAL_TIME_RUNNING,
+            flags: FLAG_DISABLED
+                | FLAG_INHERIT
+                | FLAG_EXCLUDE_KERNEL
+                | FLAG_EXCLUDE_HV
+                | if enable_on_exec {
+                    FLAG_ENABLE_ON_EXEC
+                } else {
+                    0
+                },
+            ..Default::default()
+        };
+        // SAFETY: attr is a valid perf_event_attr during the call.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_perf_event_open,
+                &attr as *const Attr,
+                pid,
+                -1,
+                -1,
+                PERF_FLAG_FD_CLOEXEC,
+            )
+        };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: fd is the newly opened one owned by nobody else.
+        Ok(std::fs::File::from(unsafe {
+            OwnedFd::from_raw_fd(fd as libc::c_int)
+        }))
+    }
+
+    /// Make the pipe of (read, write) closed on exec.
+    pub fn pipe() -> std::io::Result<(OwnedFd, OwnedFd)> {
+        let mut fds = [0; 2];
+        // SAFETY: fds has room for the two fds.
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        // SAFETY: The fds are newly opened ones owned by nobody else.
+        Ok(unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn perf_attach() {
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", "seq 1000 > /dev/null"]);
+        attach(&mut command);
+        let mut child = command.spawn().unwrap();
+        // Opened before exec, even if no counters are permitted.
+        let counters = take_counters(child.id()).unwrap();
+        assert!(child.wait().unwrap().success());
+        let items = counters.meas_items();
+        if check().is_ok() {
+            assert!(0.0 < items[&MeasItem::Instruction]);
+        }
+        assert!(take_counters(child.id()).is_none());
+
+        // Opened, but exec fails, so the pid is not known to take them.
+        {
+            let _scope = Scope::enter();
+            let mut command = std::process::Command::new("/this_will_never_exist");
+            attach(&mut command);
+            assert!(command.spawn().is_err());
+            assert_eq!(OPENED.lock().unwrap().len(), 1);
+        }
+        assert!(OPENED.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,329 @@
+// Copyright © ArkBig
+//! This file provides the plot data for gnuplot by `--export-gnuplot` and Vega-Lite by `--export-vega`.
+//!
+//! They are rendered to PNG or SVG by the standard tools offline, such as `gnuplot` and `vl2svg`.
+//! The values are in the same unit as `--export-json`, seconds for the times and bytes for the sizes.
+
+use std::fmt::Write as _;
+
+use crate::benchmark::BenchmarkResult;
+use crate::cmd::{meas_item_key, meas_item_name, MeasItem};
+use crate::reporter::Reporter;
+
+/// Writes `ITEM.dat` and `ITEM.gp` of each item to the directory when all finished.
+///
+/// The data file has one block per command and backend, separated by two blank lines
+/// to be selected by `index` in gnuplot. Each script plots the box plots and each run of the item.
+pub struct GnuplotReporter {
+    dir: std::path::PathBuf,
+}
+
+impl GnuplotReporter {
+    /// The directory is created if it does not exist.
+    pub fn create(dir: &std::path::Path) -> anyhow::Result<Self> {
+        use anyhow::Context as _;
+        std::fs::create_dir_all(dir).with_context(|| {
+            format!("Could not create the export directory `{}`", dir.display())
+        })?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+        })
+    }
+}
+
+impl Reporter for GnuplotReporter {
+    fn all_finished(&mut self, result: &BenchmarkResult) -> anyhow::Result<()> {
+        use std::io::Write as _;
+        for (name, content) in gnuplot_files(result) {
+            let mut writer = crate::reporter::create_file(&self.dir.join(name))?;
+            writer.write_all(content.as_bytes())?;
+            writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Items measured in any command, in order of the first appearance.
+fn items(result: &BenchmarkResult) -> Vec<MeasItem> {
+    let mut items = Vec::new();
+    for item in result.commands.iter().flat_map(|x| x.items()) {
+        if !items.contains(&item) {
+            items.push(item);
+        }
+    }
+    items
+}
+
+/// Names and contents of the data files and the scripts.
+fn gnuplot_files(result: &BenchmarkResult) -> Vec<(String, String)> {
+    let mut files = Vec::new();
+    for item in items(result) {
+        let series = crate::html::series(result, &item);
+        let base = file_stem(&meas_item_key(&item));
+        let title = meas_item_name(&item, 1);
+
+        let mut data = format!(
+            "# {} ({}) of each run, generated by mntime.\n\
+            # One block per command and backend, separated by two blank lines.\n",
+            meas_item_key(&item),
+            title
+        );
+        for (i, s) in series.iter().enumerate() {
+            if 0 < i {
+                data.push_str("\n\n");
+            }
+            let _ = writeln!(data, "# {}\n# run value", s.label.replace('\n', " "));
+            for (n, val) in s.samples.iter().enumerate() {
+                let _ = writeln!(data, "{} {}", n + 1, val);
+            }
+        }
+
+        let dat = format!("{}.dat", base);
+        let mut script = format!(
+            "# Plot {dat} by gnuplot in this directory, such as `gnuplot {base}.gp`.\n\
+            # The format is svg by default, or png by `gnuplot -e \"format='pngcairo'\" {base}.gp`.\n\
+            if (!exists(\"format\")) format = 'svg'\n\
+            set terminal @format size 1200,480\n\
+            set termoption noenhanced\n\
+            set output '{base}.' . (format eq 'svg' ? 'svg' : 'png')\n\
+            set multiplot layout 1,2 title {title}\n\
+            set grid ytics\n\
+            set ylabel {title}\n\
+            set style fill solid 0.5 border -1\n\
+            set style boxplot outliers pointtype 7\n\
+            unset key\n",
+            dat = dat,
+            base = base,
+            title = quote(&title)
+        );
+        let xtics: Vec<_> = series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| format!("{} {}", quote(&s.label), i + 1))
+            .collect();
+        let _ = writeln!(
+            script,
+            "set xtics ({}) rotate by -20\nset xrange [0.5:{}.5]",
+            xtics.join(", "),
+            series.len()
+        );
+        let plots: Vec<_> = series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                format!(
+                    "{} index {} using ({}):2 with boxplot lc rgb '{}'",
+                    quote(&dat),
+                    i,
+                    i + 1,
+                    s.color
+                )
+            })
+            .collect();
+        let _ = writeln!(script, "plot {}", plots.join(", \\\n     "));
+        script.push_str("set key outside bottom center horizontal\nset xtics auto norotate\nset autoscale x\nset xlabel 'run'\n");
+        let plots: Vec<_> = series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                format!(
+                    "{} index {} using 1:2 with linespoints pointtype 7 lc rgb '{}' title {}",
+                    quote(&dat),
+                    i,
+                    s.color,
+                    quote(&s.label)
+                )
+            })
+            .collect();
+        let _ = writeln!(script, "plot {}", plots.join(", \\\n     "));
+        script.push_str("unset multiplot\n");
+
+        files.push((dat, data));
+        files.push((format!("{}.gp", base), script));
+    }
+    files
+}
+
+/// Single quoted string of gnuplot, without the escape sequences.
+fn quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "''").replace('\n', " "))
+}
+
+/// Replace the characters not safe in the file names, such as of the custom metrics.
+fn file_stem(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Writes the Vega-Lite spec embedding the samples of all items when all finished.
+///
+/// The box plots and each run of the wall clock time and the maximum resident set size are charted,
+/// and the other items are in the data to change the `filter` of the spec.
+pub struct VegaReporter<W: std::io::Write> {
+    writer: W,
+}
+
+impl VegaReporter<std::io::BufWriter<std::fs::File>> {
+    pub fn create(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(Self::new(crate::reporter::create_file(path)?))
+    }
+}
+
+impl<W: std::io::Write> VegaReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: std::io::Write> Reporter for VegaReporter<W> {
+    fn all_finished(&mut self, result: &BenchmarkResult) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(&mut self.writer, &vega_spec(result))?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn vega_spec(result: &BenchmarkResult) -> serde_json::Value {
+    let mut values = Vec::new();
+    for (index, command) in result.commands.iter().enumerate() {
+        let by_backend = command.by_backend();
+        let multiple = 1 < by_backend.len();
+        for command in by_backend {
+            let label = crate::html::label(index, &command, multiple);
+            for (n, report) in command.runs.iter().enumerate() {
+                let mut items: Vec<_> = report.iter().collect();
+                items.sort_by_key(|x| meas_item_key(x.0));
+                for (item, val) in items {
+                    values.push(serde_json::json!({
+                        "command": label,
+                        "index": index + 1,
+                        "backend": command.backends[0],
+                        "run": n + 1,
+                        "item": meas_item_key(item),
+                        "value": val,
+                    }));
+                }
+            }
+        }
+    }
+    let command = |legend: bool| {
+        let mut json = serde_json::json!({ "field": "command", "type": "nominal", "sort": null });
+        if !legend {
+            json["legend"] = serde_json::Value::Null;
+        }
+        json
+    };
+    let charts: Vec<_> = crate::html::CHART_ITEMS
+        .iter()
+        .filter(|item| result.commands.iter().any(|x| !x.samples(item).is_empty()))
+        .map(|item| {
+            let title = meas_item_name(item, 1);
+            serde_json::json!({
+                "title": title,
+                "transform": [{ "filter": { "field": "item", "equal": meas_item_key(item) } }],
+                "hconcat": [
+                    {
+                        "mark": "boxplot",
+                        "encoding": {
+                            "y": { "field": "command", "type": "nominal", "sort": null, "title": null },
+                            "x": { "field": "value", "type": "quantitative", "title": title },
+                            "color": command(false),
+                        },
+                    },
+                    {
+                        "mark": { "type": "line", "point": true },
+                        "encoding": {
+                            "x": { "field": "run", "type": "quantitative", "title": "run" },
+                            "y": { "field": "value", "type": "quantitative", "title": title },
+                            "color": command(true),
+                        },
+                    },
+                ],
+            })
+        })
+        .collect();
+    serde_json::json!({
+        "$schema": "https://vega.github.io/schema/vega-lite/v5.json",
+        "description": "Benchmark results by mntime",
+        "data": { "values": values },
+        "vconcat": charts,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::benchmark::CommandResult;
+    use std::collections::HashMap;
+
+    fn sample_result() -> BenchmarkResult {
+        BenchmarkResult {
+            commands: vec![
+                CommandResult {
+                    name: String::from("it's"),
+                    command: String::from("echo it"),
+                    loops: 1,
+                    runs: vec![
+                        HashMap::from([(MeasItem::Real, 1.0), (MeasItem::MaxResident, 10.0)]),
+                        HashMap::from([(MeasItem::Real, 2.0), (MeasItem::MaxResident, 20.0)]),
+                    ],
+                    backends: vec![String::from("gnu"); 2],
+                    ..Default::default()
+                },
+                CommandResult {
+                    name: String::from("second"),
+                    command: String::from("true"),
+                    loops: 1,
+                    runs: vec![HashMap::from([(MeasItem::Real, 3.0)])],
+                    backends: vec![String::from("gnu")],
+                    ..Default::default()
+                },
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn plot_gnuplot_files() {
+        let files = gnuplot_files(&sample_result());
+        let names: Vec<_> = files.iter().map(|x| x.0.as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["Real.dat", "Real.gp", "MaxResident.dat", "MaxResident.gp"]
+        );
+        assert!(files[0]
+            .1
+            .ends_with("# #1 it's\n# run value\n1 1\n2 2\n\n\n# #2 second\n# run value\n1 3\n"));
+        let script = &files[1].1;
+        assert!(script.contains("set xtics ('#1 it''s' 1, '#2 second' 2)"));
+        assert!(script.contains("'Real.dat' index 1 using (2):2 with boxplot"));
+        assert!(script.contains("'Real.dat' index 1 using 1:2 with linespoints"));
+        // Only the first command has MaxResident.
+        assert!(!files[2].1.contains("#2 second"));
+        assert_eq!(file_stem("cpu/load avg"), "cpu_load_avg");
+    }
+
+    #[test]
+    fn plot_vega_spec() {
+        let spec = vega_spec(&sample_result());
+        assert!(spec["$schema"].as_str().unwrap().contains("vega-lite"));
+        let values = spec["data"]["values"].as_array().unwrap();
+        assert_eq!(values.len(), 5);
+        assert_eq!(values[0]["command"], "#1 it's");
+        assert_eq!(values[0]["item"], "MaxResident");
+        assert_eq!(values[4]["run"], 1);
+        assert_eq!(values[4]["value"], 3.0);
+        let charts = spec["vconcat"].as_array().unwrap();
+        assert_eq!(charts.len(), 2);
+        assert_eq!(charts[0]["transform"][0]["filter"]["equal"], "Real");
+        assert_eq!(charts[0]["hconcat"][0]["mark"], "boxplot");
+    }
+}
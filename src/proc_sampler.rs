@@ -0,0 +1,601 @@
+// Copyright © ArkBig
+//! This file provides sampling of the memory usage from [/proc][proc] while the command is running.
+//!
+//! The time command reports only the maximum, which hides whether memory spikes briefly or stays high.
+//! On other than Linux, no samples are taken because there is no /proc.
+//!
+//! [proc]:https://man7.org/linux/man-pages/man5/proc.5.html
+
+use std::collections::HashMap;
+
+use crate::cmd::MeasItem;
+
+/// Memory usage of the process tree at one moment.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct MemorySample {
+    /// Seconds since the run started.
+    pub elapsed: f64,
+    /// Resident set size in bytes.
+    pub rss: f64,
+    /// Proportional set size in bytes. 0 if smaps_rollup is not readable.
+    pub pss: f64,
+    /// Swapped out size in bytes.
+    pub swap: f64,
+    pub threads: f64,
+    /// Open file descriptors. 0 if not readable.
+    pub fds: f64,
+}
+
+/// Usage of the processes with the same executable name.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct ProcessUsage {
+    name: String,
+    /// User and system time in seconds at the last sample.
+    cpu: f64,
+    /// Resident set size in bytes at the peak.
+    peak_rss: f64,
+}
+
+/// Samples the process tree whose root is the given pid.
+pub struct ProcSampler {
+    root: u32,
+    start: std::time::Instant,
+    samples: Vec<MemorySample>,
+    /// Usage of each pid, only if per process.
+    processes: Option<HashMap<u32, ProcessUsage>>,
+    /// True to report the items of the time series, or else only the peak RSS of the tree.
+    series: bool,
+    /// cgroup of the run, whose processes are sampled instead of the descendants of the root.
+    cgroup: Option<std::path::PathBuf>,
+    /// CPU time of the reaped orphans.
+    reaped_cpu: f64,
+    /// CPU time of the whole run, to report the one of the processes missed by the samples.
+    total_cpu: Option<f64>,
+}
+
+impl ProcSampler {
+    pub fn new(root: u32) -> Self {
+        Self {
+            root,
+            start: std::time::Instant::now(),
+            samples: Vec::new(),
+            processes: None,
+            series: true,
+            cgroup: None,
+            reaped_cpu: 0.0,
+            total_cpu: None,
+        }
+    }
+
+    /// Sample the process tree of the run with the usage of each process.
+    ///
+    /// With the subreaper, this includes the orphaned ones such as daemons,
+    /// which are tracked after they are reparented to this process.
+    /// If the run has the cgroup, all processes in it are sampled instead.
+    /// The items of the time series are reported only if `series`, such as by `--sample-memory`.
+    pub fn for_process_tree(root: u32, cgroup: Option<std::path::PathBuf>, series: bool) -> Self {
+        Self {
+            processes: Some(HashMap::new()),
+            series,
+            cgroup,
+            ..Self::new(root)
+        }
+    }
+
+    /// Processes of the run at this moment.
+    fn pids(&self) -> Vec<u32> {
+        if let Some(cgroup) = &self.cgroup {
+            if let Ok(procs) = std::fs::read_to_string(cgroup.join("cgroup.procs")) {
+                return procs
+                    .split_whitespace()
+                    .filter_map(|x| x.parse().ok())
+                    .collect();
+            }
+        }
+        let children = children_map();
+        let mut roots = vec![self.root];
+        if let Some(processes) = &self.processes {
+            roots.extend(
+                self.orphans(&children)
+                    .filter(|x| processes.contains_key(x)),
+            );
+        }
+        descendants(&children, roots)
+    }
+
+    /// Children of this process other than the root, which are the orphans reparented by the subreaper
+    /// or the children of others such as the library user. Only the ones seen in the run are the orphans of it.
+    fn orphans<'a>(&self, children: &'a HashMap<u32, Vec<u32>>) -> impl Iterator<Item = u32> + 'a {
+        let root = self.root;
+        children
+            .get(&std::process::id())
+            .into_iter()
+            .flatten()
+            .copied()
+            .filter(move |&x| x != root)
+    }
+
+    /// Take one sample. Nothing is recorded if the process tree has already exited.
+    pub fn sample(&mut self) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let mut sample = MemorySample {
+            elapsed,
+            ..Default::default()
+        };
+        let mut found = false;
+        for pid in self.pids() {
+            // The process may exit at any time, so skip the ones that can not be read.
+            let Ok(status) = std::fs::read_to_string(format!("/proc/{}/status", pid)) else {
+                continue;
+            };
+            // Zombies have no memory lines.
+            if !status.contains("VmRSS:") {
+                continue;
+            }
+            found = true;
+            const KB: f64 = 1024.0;
+            let rss = status_value(&status, "VmRSS:") * KB;
+            sample.rss += rss;
+            if let Some(processes) = &mut self.processes {
+                if let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+                    let usage = processes.entry(pid).or_default();
+                    // The name changes by exec, so the last one is used.
+                    usage.name = process_name(&stat).unwrap_or_default().to_string();
+                    usage.cpu = cpu_time(&stat).unwrap_or(usage.cpu);
+                    usage.peak_rss = usage.peak_rss.max(rss);
+                }
+            }
+            sample.swap += status_value(&status, "VmSwap:") * KB;
+            sample.threads += status_value(&status, "Threads:");
+            if let Ok(smaps) = std::fs::read_to_string(format!("/proc/{}/smaps_rollup", pid)) {
+                sample.pss += status_value(&smaps, "Pss:") * KB;
+            }
+            if let Ok(fds) = std::fs::read_dir(format!("/proc/{}/fd", pid)) {
+                sample.fds += fds.count() as f64;
+            }
+        }
+        if found {
+            self.samples.push(sample);
+        }
+    }
+
+    /// Reap the exited orphans of the run, and return the sum of their I/O.
+    ///
+    /// They are reparented to this process by the subreaper, and would remain as zombies.
+    /// Only the ones seen in the run are reaped, so that the other children of this process are left to their owners.
+    /// Their CPU time and peak RSS are replaced with the exact ones of the rusage.
+    #[cfg(unix)]
+    pub fn reap_orphans(&mut self) -> HashMap<MeasItem, f64> {
+        let mut io = HashMap::new();
+        let children = children_map();
+        let orphans: Vec<_> = self.orphans(&children).collect();
+        let Some(processes) = &mut self.processes else {
+            return io;
+        };
+        for pid in orphans {
+            let Some(usage) = processes.get_mut(&pid) else {
+                continue;
+            };
+            // Read before reaping, because /proc of the zombie is removed by wait4.
+            let orphan_io = io_meas_items(pid);
+            let mut status = 0;
+            // SAFETY: rusage is a plain C struct, so all zeros is a valid value.
+            let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+            // SAFETY: WNOHANG does not block, and it does nothing if the child is running.
+            let reaped =
+                unsafe { libc::wait4(pid as libc::pid_t, &mut status, libc::WNOHANG, &mut rusage) };
+            if reaped == pid as libc::pid_t {
+                for (item, val) in orphan_io {
+                    *io.entry(item).or_insert(0.0) += val;
+                }
+                let sec = |t: &libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1_000_000.0;
+                usage.cpu = sec(&rusage.ru_utime) + sec(&rusage.ru_stime);
+                self.reaped_cpu += usage.cpu;
+                // ru_maxrss is in kilobytes on Linux.
+                usage.peak_rss = usage.peak_rss.max(rusage.ru_maxrss as f64 * 1024.0);
+            }
+        }
+        io
+    }
+
+    #[cfg(not(unix))]
+    pub fn reap_orphans(&mut self) -> HashMap<MeasItem, f64> {
+        HashMap::new()
+    }
+
+    /// Account the CPU time of the whole run from the report of it,
+    /// so that the one of the processes exited between the samples is reported as "[others] CPU time".
+    ///
+    /// The total is `cpu.stat` of the cgroup if available, or else the user and system time of the command
+    /// plus the ones of the reaped orphans.
+    pub fn account_total_cpu(&mut self, report: &HashMap<MeasItem, f64>) {
+        if self.processes.is_none() {
+            return;
+        }
+        self.total_cpu = report.get(&MeasItem::CgroupCpuUsage).copied().or_else(|| {
+            Some(report.get(&MeasItem::User)? + report.get(&MeasItem::Sys)? + self.reaped_cpu)
+        });
+    }
+
+    /// Items derived from the samples, and the breakdown by executable name if per process.
+    pub fn meas_items(&self) -> HashMap<MeasItem, f64> {
+        let mut meas_items = derived_meas_items(&self.samples);
+        if !self.series {
+            meas_items.retain(|item, _| *item == MeasItem::PeakTreeResident);
+        }
+        let Some(processes) = &self.processes else {
+            return meas_items;
+        };
+        let mut by_name = HashMap::<&str, (f64, f64, f64)>::new();
+        for usage in processes.values() {
+            let (count, cpu, peak_rss) = by_name.entry(&usage.name).or_default();
+            *count += 1.0;
+            *cpu += usage.cpu;
+            *peak_rss = usage.peak_rss.max(*peak_rss);
+        }
+        use crate::metric::MetricUnit;
+        let sampled_cpu: f64 = by_name.values().map(|x| x.1).sum();
+        for (name, (count, cpu, peak_rss)) in by_name {
+            let item = |x: &str, unit| MeasItem::Metric(format!("[{}] {}", name, x), unit);
+            meas_items.insert(item("processes", MetricUnit::Count), count);
+            meas_items.insert(item("CPU time", MetricUnit::Duration), cpu);
+            meas_items.insert(item("peak RSS", MetricUnit::Bytes), peak_rss);
+        }
+        if let Some(total_cpu) = self.total_cpu {
+            meas_items.insert(
+                MeasItem::Metric(String::from("[others] CPU time"), MetricUnit::Duration),
+                (total_cpu - sampled_cpu).max(0.0),
+            );
+        }
+        meas_items
+    }
+
+    /// The time series, which is empty if not `series`.
+    pub fn into_samples(self) -> Vec<MemorySample> {
+        if self.series {
+            self.samples
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+/// This process is the subreaper while this is alive, so that orphaned descendants are reparented to it instead of init.
+///
+/// The previous setting is restored when dropped.
+pub struct ChildSubreaper {
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    previous: libc::c_int,
+}
+
+impl ChildSubreaper {
+    #[cfg(target_os = "linux")]
+    pub fn set() -> Self {
+        let mut previous = 0;
+        // SAFETY: prctl with PR_GET_CHILD_SUBREAPER only writes the flag to the valid pointer,
+        // and PR_SET_CHILD_SUBREAPER only sets a flag of this process.
+        unsafe {
+            libc::prctl(
+                libc::PR_GET_CHILD_SUBREAPER,
+                &mut previous as *mut libc::c_int,
+            );
+            libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0);
+        }
+        Self { previous }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn set() -> Self {
+        Self { previous: 0 }
+    }
+}
+
+impl Drop for ChildSubreaper {
+    fn drop(&mut self) {
+        #[cfg(target_os = "linux")]
+        // SAFETY: prctl with PR_SET_CHILD_SUBREAPER only sets a flag of this process.
+        unsafe {
+            libc::prctl(libc::PR_SET_CHILD_SUBREAPER, self.previous, 0, 0, 0);
+        }
+    }
+}
+
+/// I/O of the process and its waited descendants from /proc/pid/io. Empty if not readable.
+///
+/// The kernel adds the I/O of the waited children to the parent,
+/// so the ones of the time command and the benchmarked command are included in the shell's.
+/// It must be read before the process is reaped, so check has_exited() beforehand.
+pub fn io_meas_items(pid: u32) -> HashMap<MeasItem, f64> {
+    parse_io(&std::fs::read_to_string(format!("/proc/{}/io", pid)).unwrap_or_default())
+}
+
+/// True if the child has exited, without reaping it so that its /proc remains.
+///
+/// True also if it is not a child, so that the caller does not wait for it forever.
+#[cfg(target_os = "linux")]
+pub fn has_exited(pid: u32) -> bool {
+    // SAFETY: siginfo_t is a plain C struct, so all zeros is a valid value.
+    let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+    // SAFETY: The pointer is valid during the call. WNOWAIT leaves the child waitable.
+    let ret = unsafe {
+        libc::waitid(
+            libc::P_PID,
+            pid as libc::id_t,
+            &mut info,
+            libc::WEXITED | libc::WNOHANG | libc::WNOWAIT,
+        )
+    };
+    // With WNOHANG, si_pid remains 0 while the child is running.
+    // SAFETY: si_pid is filled by waitid for WEXITED.
+    ret != 0 || unsafe { info.si_pid() } != 0
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn has_exited(_pid: u32) -> bool {
+    true
+}
+
+/// Items derived from the time series. Empty if there are no samples.
+fn derived_meas_items(samples: &[MemorySample]) -> HashMap<MeasItem, f64> {
+    let mut meas_items = HashMap::new();
+    let Some(peak) = samples.iter().max_by(|a, b| a.rss.total_cmp(&b.rss)) else {
+        return meas_items;
+    };
+    let max = |f: fn(&MemorySample) -> f64| samples.iter().map(f).fold(0.0, f64::max);
+    meas_items.insert(
+        MeasItem::MeanResident,
+        samples.iter().map(|x| x.rss).sum::<f64>() / samples.len() as f64,
+    );
+    meas_items.insert(MeasItem::TimeToPeakResident, peak.elapsed);
+    meas_items.insert(MeasItem::PeakTreeResident, peak.rss);
+    meas_items.insert(MeasItem::PeakProportional, max(|x| x.pss));
+    meas_items.insert(MeasItem::PeakSwap, max(|x| x.swap));
+    meas_items.insert(MeasItem::PeakThreads, max(|x| x.threads));
+    meas_items.insert(MeasItem::PeakOpenFiles, max(|x| x.fds));
+    meas_items
+}
+
+/// Children of each pid from /proc.
+fn children_map() -> HashMap<u32, Vec<u32>> {
+    let mut children = HashMap::<u32, Vec<u32>>::new();
+    if let Ok(entries) = std::fs::read_dir("/proc") {
+        for entry in entries.flatten() {
+            let Some(pid) = entry.file_name().to_str().and_then(|x| x.parse().ok()) else {
+                continue;
+            };
+            let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) else {
+                continue;
+            };
+            if let Some(ppid) = parent_pid(&stat) {
+                children.entry(ppid).or_default().push(pid);
+            }
+        }
+    }
+    children
+}
+
+/// The roots and all their descendants.
+fn descendants(children: &HashMap<u32, Vec<u32>>, roots: Vec<u32>) -> Vec<u32> {
+    let mut tree = roots;
+    let mut i = 0;
+    while i < tree.len() {
+        if let Some(x) = children.get(&tree[i]) {
+            tree.extend(x);
+        }
+        i += 1;
+    }
+    tree
+}
+
+/// The 4th field of /proc/pid/stat. The 2nd one is the name in parentheses, which may contain spaces.
+fn parent_pid(stat: &str) -> Option<u32> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    rest.split_whitespace().nth(1)?.parse().ok()
+}
+
+/// The 2nd field of /proc/pid/stat without parentheses.
+fn process_name(stat: &str) -> Option<&str> {
+    let (_, rest) = stat.split_once('(')?;
+    let (name, _) = rest.rsplit_once(')')?;
+    Some(name)
+}
+
+/// The total of the 14th and 15th fields of /proc/pid/stat in seconds.
+fn cpu_time(stat: &str) -> Option<f64> {
+    let (_, rest) = stat.rsplit_once(')')?;
+    let mut fields = rest.split_whitespace().skip(11);
+    let utime: f64 = fields.next()?.parse().ok()?;
+    let stime: f64 = fields.next()?.parse().ok()?;
+    Some((utime + stime) / clock_ticks())
+}
+
+#[cfg(unix)]
+fn clock_ticks() -> f64 {
+    // SAFETY: sysconf has no side effects.
+    unsafe { libc::sysconf(libc::_SC_CLK_TCK) as f64 }
+}
+
+#[cfg(not(unix))]
+fn clock_ticks() -> f64 {
+    100.0
+}
+
+/// The number after the key, such as `VmRSS:     1234 kB`. 0 if not found.
+fn status_value(text: &str, key: &str) -> f64 {
+    text.lines()
+        .find_map(|line| line.strip_prefix(key))
+        .and_then(|rest| rest.split_whitespace().next())
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(0.0)
+}
+
+/// Items of the content of /proc/pid/io.
+fn parse_io(text: &str) -> HashMap<MeasItem, f64> {
+    [
+        ("rchar:", MeasItem::ReadChars),
+        ("wchar:", MeasItem::WriteChars),
+        ("syscr:", MeasItem::ReadSyscalls),
+        ("syscw:", MeasItem::WriteSyscalls),
+        ("read_bytes:", MeasItem::StorageRead),
+        ("write_bytes:", MeasItem::StorageWrite),
+    ]
+    .into_iter()
+    .filter_map(|(key, item)| {
+        let val = text.lines().find_map(|line| line.strip_prefix(key))?;
+        Some((item, val.trim().parse().ok()?))
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// The root and all its descendants.
+    fn process_tree(root: u32) -> Vec<u32> {
+        descendants(&children_map(), vec![root])
+    }
+
+    #[test]
+    fn proc_parse() {
+        let stat = "1234 (a (b) c) S 42 1234 1234 0 -1 4194560 94 0 0 0 300 100 0 0 20 0 1";
+        assert_eq!(parent_pid(stat), Some(42));
+        assert_eq!(process_name(stat), Some("a (b) c"));
+        assert_eq!(cpu_time(stat), Some(400.0 / clock_ticks()));
+
+        let status = "Name:\tsleep\nVmRSS:\t    1248 kB\nThreads:\t3\n";
+        assert_eq!(status_value(status, "VmRSS:"), 1248.0);
+        assert_eq!(status_value(status, "Threads:"), 3.0);
+        assert_eq!(status_value(status, "VmSwap:"), 0.0);
+
+        let io = "rchar: 4096\nwchar: 10\nsyscr: 3\nsyscw: 1\nread_bytes: 0\nwrite_bytes: 8192\ncancelled_write_bytes: 0\n";
+        let items = parse_io(io);
+        assert_eq!(items.len(), 6);
+        assert_eq!(items[&MeasItem::ReadChars], 4096.0);
+        assert_eq!(items[&MeasItem::WriteSyscalls], 1.0);
+        assert_eq!(items[&MeasItem::StorageWrite], 8192.0);
+        assert!(parse_io("").is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn proc_io_of_exited_child() {
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "head -c 100000 /dev/zero > /dev/null"])
+            .spawn()
+            .unwrap();
+        while !has_exited(child.id()) {
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        }
+        // The waited head is included in the shell.
+        let items = io_meas_items(child.id());
+        assert!(100000.0 <= items[&MeasItem::ReadChars]);
+        assert!(child.wait().unwrap().success());
+        assert!(io_meas_items(child.id()).is_empty());
+    }
+
+    #[test]
+    fn proc_derived_meas_items() {
+        assert!(derived_meas_items(&[]).is_empty());
+
+        let sample = |elapsed, rss| MemorySample {
+            elapsed,
+            rss,
+            threads: 1.0,
+            ..Default::default()
+        };
+        let items = derived_meas_items(&[sample(0.0, 1.0), sample(0.1, 5.0), sample(0.2, 3.0)]);
+        assert_eq!(items[&MeasItem::MeanResident], 3.0);
+        assert_eq!(items[&MeasItem::TimeToPeakResident], 0.1);
+        assert_eq!(items[&MeasItem::PeakTreeResident], 5.0);
+        assert_eq!(items[&MeasItem::PeakThreads], 1.0);
+    }
+
+    #[test]
+    fn proc_sample_process_tree() {
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 1 & wait"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        assert!(2 <= process_tree(child.id()).len());
+
+        let mut sampler = ProcSampler::new(child.id());
+        sampler.sample();
+        child.kill().unwrap();
+        child.wait().unwrap();
+        // Not recorded after exited.
+        sampler.sample();
+        let samples = sampler.into_samples();
+        assert_eq!(samples.len(), 1);
+        assert!(0.0 < samples[0].rss);
+        assert!(2.0 <= samples[0].threads);
+    }
+
+    #[test]
+    fn proc_sample_per_process() {
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 1 & sleep 1 & wait"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let mut sampler = ProcSampler::for_process_tree(child.id(), None, false);
+        sampler.sample();
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let items = sampler.meas_items();
+        let processes = |name: &str| {
+            let item = MeasItem::Metric(
+                format!("[{}] processes", name),
+                crate::metric::MetricUnit::Count,
+            );
+            items.get(&item).copied()
+        };
+        // Only the tree of the run, even if other tests run processes at the same time.
+        assert_eq!(processes("sh"), Some(1.0));
+        assert_eq!(processes("sleep"), Some(2.0));
+        assert!(items[&MeasItem::PeakTreeResident] > 0.0);
+        // Without the time series.
+        assert!(!items.contains_key(&MeasItem::MeanResident));
+        assert!(sampler.into_samples().is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn proc_reap_only_orphans_of_run() {
+        let _subreaper = ChildSubreaper::set();
+        let mut other = std::process::Command::new("true").spawn().unwrap();
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "sleep 0.3 & sleep 0.1"])
+            .spawn()
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        let mut sampler = ProcSampler::for_process_tree(child.id(), None, false);
+        sampler.sample();
+        child.wait().unwrap();
+        // The orphan is reparented to this process, and still sampled.
+        sampler.sample();
+        assert_eq!(sampler.samples.len(), 2);
+        std::thread::sleep(std::time::Duration::from_millis(400));
+        sampler.reap_orphans();
+        let processes = sampler.processes.as_ref().unwrap();
+        assert!(2 <= processes.len());
+        for pid in processes.keys() {
+            assert!(!std::path::Path::new(&format!("/proc/{}", pid)).exists());
+        }
+        // The exit status of the other child is not taken.
+        assert!(other.wait().unwrap().success());
+
+        sampler.account_total_cpu(&HashMap::from([
+            (MeasItem::User, 10.0),
+            (MeasItem::Sys, 0.0),
+        ]));
+        let items = sampler.meas_items();
+        let item = |name: &str| {
+            items[&MeasItem::Metric(name.to_string(), crate::metric::MetricUnit::Duration)]
+        };
+        assert!(9.0 < item("[others] CPU time"));
+        assert!(item("[sleep] CPU time") < 1.0);
+    }
+}
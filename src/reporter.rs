@@ -0,0 +1,666 @@
+// Copyright © ArkBig
+//! This file provides the reporters that output the measured results.
+
+use std::collections::HashMap;
+
+use crate::benchmark::{BenchmarkCommand, BenchmarkResult, CommandResult};
+use crate::cmd::{meas_item_key, MeasItem};
+use crate::metadata::Metadata;
+
+/// Receives the progress of the benchmark and outputs it in its own format.
+///
+/// All callbacks do nothing by default, so implement only the ones needed.
+/// Multiple reporters can be combined with `Vec<Box<dyn Reporter>>`.
+pub trait Reporter {
+    /// Called once before the first command, with where and how it is measured.
+    fn all_start(&mut self, _metadata: &Metadata) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called before the first run of the index-th command.
+    fn benchmark_start(
+        &mut self,
+        _index: usize,
+        _command: &BenchmarkCommand,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called each time the n-th run of the index-th command finishes.
+    ///
+    /// `backend` is the name of the backend that measured the report.
+    fn run_finished(
+        &mut self,
+        _index: usize,
+        _n: u16,
+        _backend: &str,
+        _report: &HashMap<MeasItem, f64>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after the last run of the index-th command.
+    fn command_finished(&mut self, _index: usize, _result: &CommandResult) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    /// Called after all commands are finished.
+    fn all_finished(&mut self, _result: &BenchmarkResult) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// Calls each reporter in order. If one fails, the rest are not called.
+impl Reporter for Vec<Box<dyn Reporter>> {
+    fn all_start(&mut self, metadata: &Metadata) -> anyhow::Result<()> {
+        self.iter_mut().try_for_each(|x| x.all_start(metadata))
+    }
+
+    fn benchmark_start(&mut self, index: usize, command: &BenchmarkCommand) -> anyhow::Result<()> {
+        self.iter_mut()
+            .try_for_each(|x| x.benchmark_start(index, command))
+    }
+
+    fn run_finished(
+        &mut self,
+        index: usize,
+        n: u16,
+        backend: &str,
+        report: &HashMap<MeasItem, f64>,
+    ) -> anyhow::Result<()> {
+        self.iter_mut()
+            .try_for_each(|x| x.run_finished(index, n, backend, report))
+    }
+
+    fn command_finished(&mut self, index: usize, result: &CommandResult) -> anyhow::Result<()> {
+        self.iter_mut()
+            .try_for_each(|x| x.command_finished(index, result))
+    }
+
+    fn all_finished(&mut self, result: &BenchmarkResult) -> anyhow::Result<()> {
+        self.iter_mut().try_for_each(|x| x.all_finished(result))
+    }
+}
+
+/// Report the result measured beforehand, such as imported, in the same order as measuring.
+///
+/// all_start is called only if it has the metadata.
+pub(crate) fn replay(result: &BenchmarkResult, reporter: &mut dyn Reporter) -> anyhow::Result<()> {
+    if let Some(metadata) = &result.metadata {
+        reporter.all_start(metadata)?;
+    }
+    for (index, command) in result.commands.iter().enumerate() {
+        let target = BenchmarkCommand::new(command.command.clone()).name(command.name.clone());
+        reporter.benchmark_start(index, &target)?;
+        for (n, (report, backend)) in command.runs.iter().zip(&command.backends).enumerate() {
+            reporter.run_finished(index, n as u16, backend, report)?;
+        }
+        reporter.command_finished(index, command)?;
+    }
+    reporter.all_finished(result)
+}
+
+/// Create the file to export to.
+pub(crate) fn create_file(
+    path: &std::path::Path,
+) -> anyhow::Result<std::io::BufWriter<std::fs::File>> {
+    use anyhow::Context as _;
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("Could not create the export file `{}`", path.display()))?;
+    Ok(std::io::BufWriter::new(file))
+}
+
+/// Writes statistics and samples of all commands as one JSON document when all finished.
+///
+/// There is one entry per command and backend, so a command measured
+/// by multiple backends alternately has the entries with the same `index`.
+pub struct JsonReporter<W: std::io::Write> {
+    writer: W,
+}
+
+impl JsonReporter<std::io::BufWriter<std::fs::File>> {
+    pub fn create(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(Self::new(create_file(path)?))
+    }
+}
+
+impl<W: std::io::Write> JsonReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+}
+
+impl<W: std::io::Write> Reporter for JsonReporter<W> {
+    fn all_finished(&mut self, result: &BenchmarkResult) -> anyhow::Result<()> {
+        serde_json::to_writer_pretty(&mut self.writer, &to_json(result))?;
+        writeln!(self.writer)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+fn to_json(result: &BenchmarkResult) -> serde_json::Value {
+    let commands: Vec<_> = result
+        .commands
+        .iter()
+        .enumerate()
+        .flat_map(|(index, command)| {
+            let mut by_backend = command.by_backend();
+            // Skipped or interrupted before the first run, so it has no backend.
+            if by_backend.is_empty() {
+                by_backend.push(command.clone());
+            }
+            by_backend.into_iter().map(move |x| (index, x))
+        })
+        .map(|(index, command)| {
+            let items: serde_json::Map<_, _> = command
+                .items()
+                .iter()
+                .map(|item| {
+                    let stats = command.stats(item);
+                    (
+                        meas_item_key(item),
+                        serde_json::json!({
+                            "mean": stats.mean,
+                            "stdev": stats.stdev,
+                            "median": stats.median(),
+                            "min": stats.min(),
+                            "max": stats.max(),
+                            "count": stats.count(),
+                            "outlier_count": stats.outlier_count,
+                            "mean_excluding_outlier": stats.mean_excluding_outlier,
+                            "stdev_excluding_outlier": stats.stdev_excluding_outlier,
+                            "samples": command.samples(item),
+                        }),
+                    )
+                })
+                .collect();
+            let mut json = serde_json::json!({
+                "index": index + 1,
+                "name": command.name,
+                "command": command.command,
+                "backend": command.backends.first(),
+                "loops": command.loops,
+                "runs": command.runs.len(),
+                "partial": command.partial,
+                "items": items,
+            });
+            if command.memory_series.iter().any(|x| !x.is_empty()) {
+                json["memory_series"] = command
+                    .memory_series
+                    .iter()
+                    .map(|series| {
+                        series
+                            .iter()
+                            .map(|x| {
+                                serde_json::json!({
+                                    "elapsed": x.elapsed,
+                                    "rss": x.rss,
+                                    "pss": x.pss,
+                                    "swap": x.swap,
+                                    "threads": x.threads,
+                                    "fds": x.fds,
+                                })
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                    .collect::<Vec<_>>()
+                    .into();
+            }
+            if let (Some(before), Some(after)) = (&command.system_before, &command.system_after) {
+                json["system"] = serde_json::json!({
+                    "before": system_to_json(before),
+                    "after": system_to_json(after),
+                });
+            }
+            json
+        })
+        .collect();
+    let mut json = serde_json::json!({
+        "commands": commands,
+        "interrupted": result.interrupted,
+    });
+    if let Some(metadata) = &result.metadata {
+        json["metadata"] = metadata_to_json(metadata);
+    }
+    json
+}
+
+fn metadata_to_json(metadata: &Metadata) -> serde_json::Value {
+    serde_json::json!({
+        "version": metadata.version,
+        "timestamp": metadata.timestamp,
+        "hostname": metadata.hostname,
+        "kernel": metadata.kernel,
+        "cpu_model": metadata.cpu_model,
+        "cores": metadata.cores,
+        "total_memory": metadata.total_memory,
+        "backends": metadata
+            .backends
+            .iter()
+            .map(|(name, version)| serde_json::json!({ "name": name, "version": version }))
+            .collect::<Vec<_>>(),
+        "shell": metadata.shell,
+        "runs": metadata.runs,
+        "loops": metadata.loops,
+        "cpu_affinity": metadata.cpu_affinity,
+        "nice": metadata.nice,
+        "sched": metadata.sched,
+        "memory_limit": metadata.memory_limit,
+        "cpu_quota": metadata.cpu_quota,
+        "cold_cache": metadata.cold_cache,
+        "drop_caches": metadata.drop_caches,
+    })
+}
+
+fn system_to_json(state: &crate::system::SystemState) -> serde_json::Value {
+    serde_json::json!({
+        "load_average": state.load_average,
+        "online_cpus": state.online_cpus,
+        "governors": state.governors,
+        "turbo": state.turbo,
+        "cpu_pressure": state.cpu_pressure,
+        "memory_pressure": state.memory_pressure,
+        "io_pressure": state.io_pressure,
+    })
+}
+
+/// Writes each sample as one CSV row as soon as the run finishes.
+///
+/// The columns are `command_index,name,backend,run,item,value`, with 1-based indexes.
+/// The metadata is written before the header as comment lines such as `# kernel: Linux 6.8.0`.
+pub struct CsvReporter<W: std::io::Write> {
+    writer: W,
+    names: Vec<String>,
+    header_written: bool,
+}
+
+impl CsvReporter<std::io::BufWriter<std::fs::File>> {
+    pub fn create(path: &std::path::Path) -> anyhow::Result<Self> {
+        Ok(Self::new(create_file(path)?))
+    }
+}
+
+impl<W: std::io::Write> CsvReporter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            names: Vec::new(),
+            header_written: false,
+        }
+    }
+
+    /// The header is written lazily, so that the metadata can precede it.
+    fn write_header(&mut self) -> anyhow::Result<()> {
+        if !self.header_written {
+            writeln!(self.writer, "command_index,name,backend,run,item,value")?;
+            self.writer.flush()?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+}
+
+impl<W: std::io::Write> Reporter for CsvReporter<W> {
+    fn all_start(&mut self, metadata: &Metadata) -> anyhow::Result<()> {
+        if !self.header_written {
+            let unknown = || String::new();
+            let backends: Vec<_> = metadata
+                .backends
+                .iter()
+                .map(|(name, version)| match version {
+                    Some(version) => format!("{} ({})", name, version),
+                    None => name.clone(),
+                })
+                .collect();
+            for (key, value) in [
+                ("version", metadata.version.clone()),
+                ("timestamp", metadata.timestamp.clone()),
+                (
+                    "hostname",
+                    metadata.hostname.clone().unwrap_or_else(unknown),
+                ),
+                ("kernel", metadata.kernel.clone()),
+                (
+                    "cpu_model",
+                    metadata.cpu_model.clone().unwrap_or_else(unknown),
+                ),
+                ("cores", metadata.cores.to_string()),
+                (
+                    "total_memory",
+                    metadata
+                        .total_memory
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(unknown),
+                ),
+                ("backends", backends.join(", ")),
+                ("shell", metadata.shell.clone()),
+                ("runs", metadata.runs.to_string()),
+                ("loops", metadata.loops.to_string()),
+                (
+                    "cpu_affinity",
+                    crate::sched::format_cpu_list(&metadata.cpu_affinity),
+                ),
+                (
+                    "nice",
+                    metadata.nice.map(|x| x.to_string()).unwrap_or_else(unknown),
+                ),
+                ("sched", metadata.sched.clone().unwrap_or_else(unknown)),
+                (
+                    "memory_limit",
+                    metadata
+                        .memory_limit
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(unknown),
+                ),
+                (
+                    "cpu_quota",
+                    metadata
+                        .cpu_quota
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(unknown),
+                ),
+                (
+                    "cold_cache",
+                    metadata
+                        .cold_cache
+                        .iter()
+                        .map(|x| x.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                ),
+                ("drop_caches", metadata.drop_caches.to_string()),
+            ] {
+                writeln!(
+                    self.writer,
+                    "# {}: {}",
+                    key,
+                    value.replace(['\n', '\r'], " ")
+                )?;
+            }
+        }
+        self.write_header()
+    }
+
+    fn benchmark_start(&mut self, index: usize, command: &BenchmarkCommand) -> anyhow::Result<()> {
+        self.write_header()?;
+        if self.names.len() <= index {
+            self.names.resize(index + 1, String::new());
+        }
+        self.names[index] = command.display_name().to_string();
+        Ok(())
+    }
+
+    fn run_finished(
+        &mut self,
+        index: usize,
+        n: u16,
+        backend: &str,
+        report: &HashMap<MeasItem, f64>,
+    ) -> anyhow::Result<()> {
+        self.write_header()?;
+        // The name is empty if the command is not started.
+        let name = csv_field(self.names.get(index).map_or("", |x| x));
+        let mut items: Vec<_> = report.iter().collect();
+        items.sort_by_key(|x| meas_item_key(x.0));
+        for (item, val) in items {
+            writeln!(
+                self.writer,
+                "{},{},{},{},{},{}",
+                index + 1,
+                name,
+                csv_field(backend),
+                n + 1,
+                csv_field(&meas_item_key(item)),
+                val
+            )?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    fn command_finished(&mut self, index: usize, result: &CommandResult) -> anyhow::Result<()> {
+        // Rows of the runs so far are already written, so it is noted after them.
+        if result.partial {
+            writeln!(
+                self.writer,
+                "# partial: {},{}",
+                index + 1,
+                csv_field(&result.name).replace(['\n', '\r'], " ")
+            )?;
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+
+    fn all_finished(&mut self, result: &BenchmarkResult) -> anyhow::Result<()> {
+        // Rows of the runs so far are already written, so it is noted at the end.
+        if result.interrupted {
+            writeln!(self.writer, "# interrupted: true")?;
+            self.writer.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Quote the field if it contains characters that need it.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sample_result() -> BenchmarkResult {
+        BenchmarkResult {
+            commands: vec![CommandResult {
+                name: String::from("first, \"one\""),
+                command: String::from("echo 1"),
+                loops: 1,
+                runs: vec![
+                    HashMap::from([(MeasItem::Real, 1.0), (MeasItem::ExitStatus, 0.0)]),
+                    HashMap::from([(MeasItem::Real, 3.0), (MeasItem::ExitStatus, 0.0)]),
+                    HashMap::from([(MeasItem::Real, 5.0), (MeasItem::ExitStatus, 0.0)]),
+                ],
+                backends: vec![
+                    String::from("gnu"),
+                    String::from("gnu"),
+                    String::from("bsd"),
+                ],
+                system_before: Some(crate::system::SystemState {
+                    online_cpus: 4,
+                    ..Default::default()
+                }),
+                system_after: Some(crate::system::SystemState {
+                    cpu_pressure: Some(1.5),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            metadata: Some(Metadata {
+                version: String::from("1.2.3"),
+                hostname: Some(String::from("host")),
+                backends: vec![
+                    (String::from("gnu"), Some(String::from("GNU Time 1.9"))),
+                    (String::from("bsd"), None),
+                ],
+                runs: 3,
+                loops: 1,
+                cpu_affinity: vec![0, 1],
+                memory_limit: Some(536870912),
+                cold_cache: vec![
+                    std::path::PathBuf::from("/data"),
+                    std::path::PathBuf::from("/tmp/a b"),
+                ],
+                ..Default::default()
+            }),
+            interrupted: false,
+        }
+    }
+
+    #[test]
+    fn json_reporter_output() {
+        let result = sample_result();
+        let mut buf = Vec::new();
+        JsonReporter::new(&mut buf).all_finished(&result).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        let command = &json["commands"][0];
+        assert_eq!(command["index"], 1);
+        assert_eq!(command["name"], "first, \"one\"");
+        assert_eq!(command["backend"], "gnu");
+        assert_eq!(command["runs"], 2);
+        assert_eq!(command["partial"], false);
+        assert_eq!(command["items"]["Real"]["mean"], 2.0);
+        assert_eq!(command["items"]["Real"]["samples"][1], 3.0);
+        assert_eq!(command["items"]["ExitStatus"]["count"], 2);
+        assert_eq!(command["system"]["before"]["online_cpus"], 4);
+        assert_eq!(command["system"]["after"]["cpu_pressure"], 1.5);
+        let command = &json["commands"][1];
+        assert_eq!(command["index"], 1);
+        assert_eq!(command["backend"], "bsd");
+        assert_eq!(command["runs"], 1);
+        assert_eq!(command["items"]["Real"]["mean"], 5.0);
+        let metadata = &json["metadata"];
+        assert_eq!(metadata["version"], "1.2.3");
+        assert_eq!(metadata["hostname"], "host");
+        assert_eq!(metadata["cpu_model"], serde_json::Value::Null);
+        assert_eq!(metadata["backends"][0]["version"], "GNU Time 1.9");
+        assert_eq!(metadata["runs"], 3);
+        assert_eq!(metadata["cpu_affinity"], serde_json::json!([0, 1]));
+        assert_eq!(metadata["nice"], serde_json::Value::Null);
+        assert_eq!(metadata["memory_limit"], 536870912);
+        assert_eq!(metadata["cold_cache"][1], "/tmp/a b");
+        assert_eq!(metadata["drop_caches"], false);
+        assert_eq!(json["interrupted"], false);
+    }
+
+    #[test]
+    fn json_reporter_partial_without_runs() {
+        let mut result = sample_result();
+        result.commands.push(CommandResult {
+            name: String::from("skipped"),
+            command: String::from("sleep 10"),
+            loops: 1,
+            partial: true,
+            ..Default::default()
+        });
+        result.interrupted = true;
+        let json = to_json(&result);
+        let command = &json["commands"][2];
+        assert_eq!(command["index"], 2);
+        assert_eq!(command["name"], "skipped");
+        assert_eq!(command["backend"], serde_json::Value::Null);
+        assert_eq!(command["runs"], 0);
+        assert_eq!(command["partial"], true);
+        assert_eq!(command["items"], serde_json::json!({}));
+        assert_eq!(json["interrupted"], true);
+    }
+
+    #[test]
+    fn csv_reporter_output() {
+        let result = sample_result();
+        let mut buf = Vec::new();
+        {
+            let mut reporter = CsvReporter::new(&mut buf);
+            reporter
+                .all_start(result.metadata.as_ref().unwrap())
+                .unwrap();
+            let command = BenchmarkCommand::new("echo 1").name("first, \"one\"");
+            reporter.benchmark_start(0, &command).unwrap();
+            let command_result = &result.commands[0];
+            for (n, report) in command_result.runs.iter().enumerate() {
+                let backend = &command_result.backends[n];
+                reporter.run_finished(0, n as u16, backend, report).unwrap();
+            }
+            let partial = CommandResult {
+                partial: true,
+                ..command_result.clone()
+            };
+            reporter.command_finished(0, &partial).unwrap();
+            let interrupted = BenchmarkResult {
+                interrupted: true,
+                ..result.clone()
+            };
+            reporter.all_finished(&interrupted).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "# version: 1.2.3\n\
+            # timestamp: \n\
+            # hostname: host\n\
+            # kernel: \n\
+            # cpu_model: \n\
+            # cores: 0\n\
+            # total_memory: \n\
+            # backends: gnu (GNU Time 1.9), bsd\n\
+            # shell: \n\
+            # runs: 3\n\
+            # loops: 1\n\
+            # cpu_affinity: 0-1\n\
+            # nice: \n\
+            # sched: \n\
+            # memory_limit: 536870912\n\
+            # cpu_quota: \n\
+            # cold_cache: /data, /tmp/a b\n\
+            # drop_caches: false\n\
+            command_index,name,backend,run,item,value\n\
+            1,\"first, \"\"one\"\"\",gnu,1,ExitStatus,0\n\
+            1,\"first, \"\"one\"\"\",gnu,1,Real,1\n\
+            1,\"first, \"\"one\"\"\",gnu,2,ExitStatus,0\n\
+            1,\"first, \"\"one\"\"\",gnu,2,Real,3\n\
+            1,\"first, \"\"one\"\"\",bsd,3,ExitStatus,0\n\
+            1,\"first, \"\"one\"\"\",bsd,3,Real,5\n\
+            # partial: 1,\"first, \"\"one\"\"\"\n\
+            # interrupted: true\n"
+        );
+    }
+
+    #[test]
+    fn csv_reporter_partial() {
+        let mut buf = Vec::new();
+        {
+            let mut reporter = CsvReporter::new(&mut buf);
+            let report = HashMap::from([(MeasItem::Real, 1.0)]);
+            for (index, name, partial) in [(0, "skipped", true), (1, "full", false)] {
+                reporter
+                    .benchmark_start(index, &BenchmarkCommand::new(name))
+                    .unwrap();
+                reporter.run_finished(index, 0, "native", &report).unwrap();
+                let command_result = CommandResult {
+                    name: String::from(name),
+                    partial,
+                    ..Default::default()
+                };
+                reporter.command_finished(index, &command_result).unwrap();
+            }
+            reporter.all_finished(&BenchmarkResult::default()).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "command_index,name,backend,run,item,value\n\
+            1,skipped,native,1,Real,1\n\
+            # partial: 1,skipped\n\
+            2,full,native,1,Real,1\n"
+        );
+    }
+
+    #[test]
+    fn csv_reporter_without_start() {
+        let mut buf = Vec::new();
+        {
+            let mut reporter = CsvReporter::new(&mut buf);
+            let report = HashMap::from([(MeasItem::Real, 1.0)]);
+            reporter.run_finished(1, 0, "native", &report).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "command_index,name,backend,run,item,value\n\
+            2,,native,1,Real,1\n"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+// Copyright © ArkBig
+//! This file provides the native backend that measures with the rusage of [wait4(2)][wait4].
+//!
+//! No time command is needed, and the shell process is measured together with its children.
+//!
+//! [wait4]:https://man7.org/linux/man-pages/man2/wait4.2.html
+
+use std::collections::HashMap;
+
+use crate::cmd::{BackendFactory, ExecOptions, MeasItem, MeasureBackend, ReadyStatus};
+
+#[derive(thiserror::Error, Debug)]
+enum RusageError {
+    #[error("Execution command is not finished yet. This is a bug in the source code.")]
+    NotFinished,
+}
+
+/// Candidates of the native backend in order of fallback.
+pub fn native_candidates(benchmark: &crate::benchmark::Benchmark) -> Vec<BackendFactory<'_>> {
+    vec![Box::new(move || Ok(Box::new(RusageCmd::new(benchmark))))]
+}
+
+/// Executes the command with the shell and waits for it with wait4(2).
+pub struct RusageCmd {
+    sh: String,
+    sh_arg: String,
+    process: Option<std::process::Child>,
+    start: std::time::Instant,
+    exited: Option<std::thread::JoinHandle<std::time::Instant>>,
+    meas_report: Option<HashMap<MeasItem, f64>>,
+    stdout: Option<std::thread::JoinHandle<String>>,
+    stderr: Option<std::thread::JoinHandle<String>>,
+}
+
+impl RusageCmd {
+    pub fn new(benchmark: &crate::benchmark::Benchmark) -> Self {
+        Self {
+            sh: benchmark.shell.clone(),
+            sh_arg: benchmark.shell_arg.clone(),
+            process: None,
+            start: std::time::Instant::now(),
+            exited: None,
+            meas_report: None,
+            stdout: None,
+            stderr: None,
+        }
+    }
+}
+
+impl MeasureBackend for RusageCmd {
+    fn name(&self) -> &str {
+        "native"
+    }
+
+    /// Always available, because it is a system call.
+    fn ready_status(&mut self) -> ReadyStatus {
+        ReadyStatus::Ready
+    }
+
+    fn execute(&mut self, command: &str, options: &ExecOptions) -> anyhow::Result<()> {
+        self.meas_report = None;
+        self.start = std::time::Instant::now();
+        let stderr = if options.capture_output {
+            std::process::Stdio::piped()
+        } else {
+            std::process::Stdio::null()
+        };
+        let mut process = crate::cmd::spawn(
+            &mut crate::cmd::new_command(&self.sh, &[&self.sh_arg, command], options),
+            crate::cmd::stdout(options),
+            stderr,
+        )?;
+        self.stdout = crate::cmd::read_in_background(process.stdout.take());
+        self.stderr = crate::cmd::read_in_background(process.stderr.take());
+        self.exited = Some(wait_exit_in_background(process.id()));
+        self.process = Some(process);
+        Ok(())
+    }
+
+    fn is_finished(&mut self) -> bool {
+        if self.meas_report.is_some() {
+            return true;
+        }
+        let Some(process) = &self.process else {
+            return false;
+        };
+        // The exit time is taken by the waiting thread, not by this poll, which may be a tick later.
+        if !self.exited.as_ref().is_some_and(|x| x.is_finished()) {
+            return false;
+        }
+        let elapsed = (self.exited.take().unwrap())
+            .join()
+            .unwrap_or_else(|_| std::time::Instant::now())
+            .duration_since(self.start)
+            .as_secs_f64();
+        let mut status = 0;
+        // SAFETY: rusage is a plain C struct, so all zeros is a valid value.
+        let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+        // SAFETY: The pointers are valid during the call.
+        // The child has exited, so WNOHANG does not block.
+        let pid = unsafe {
+            libc::wait4(
+                process.id() as libc::pid_t,
+                &mut status,
+                libc::WNOHANG,
+                &mut rusage,
+            )
+        };
+        if pid <= 0 {
+            return false;
+        }
+        // The child is reaped by wait4, so it must not be waited or killed by std anymore.
+        self.process = None;
+        self.meas_report = Some(rusage_to_meas_items(elapsed, status, &rusage));
+        true
+    }
+
+    fn get_report(&mut self) -> anyhow::Result<&HashMap<MeasItem, f64>> {
+        anyhow::ensure!(self.is_finished(), RusageError::NotFinished);
+        Ok(self.meas_report.as_ref().unwrap())
+    }
+
+    fn kill(&mut self) -> anyhow::Result<()> {
+        use anyhow::Context as _;
+        if let Some(mut process) = self.process.take() {
+            process.kill().context("Could not kill the process.")?;
+            process
+                .wait()
+                .context("Could not wait the killed process.")?;
+        }
+        if let Some(exited) = self.exited.take() {
+            let _ = exited.join();
+        }
+        Ok(())
+    }
+
+    fn get_output(&mut self) -> anyhow::Result<String> {
+        anyhow::ensure!(self.is_finished(), RusageError::NotFinished);
+        let mut output = String::new();
+        for handle in [self.stdout.take(), self.stderr.take()]
+            .into_iter()
+            .flatten()
+        {
+            output += &crate::cmd::join_output(handle);
+        }
+        Ok(output)
+    }
+
+    fn pid(&self) -> Option<u32> {
+        self.process.as_ref().map(|x| x.id())
+    }
+}
+
+/// Waits for the child to exit on a thread, and returns the time of the exit.
+///
+/// The child is not reaped, so that its /proc remains until the rusage is taken by wait4(2).
+fn wait_exit_in_background(pid: u32) -> std::thread::JoinHandle<std::time::Instant> {
+    std::thread::spawn(move || {
+        // SAFETY: siginfo_t is a plain C struct, so all zeros is a valid value.
+        let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+        // SAFETY: The pointer is valid during the call. WNOWAIT leaves the child waitable.
+        // It returns an error if the child is reaped by kill(), and then the time is not used.
+        unsafe {
+            libc::waitid(
+                libc::P_PID,
+                pid as libc::id_t,
+                &mut info,
+                libc::WEXITED | libc::WNOWAIT,
+            )
+        };
+        std::time::Instant::now()
+    })
+}
+
+fn rusage_to_meas_items(
+    elapsed: f64,
+    status: libc::c_int,
+    rusage: &libc::rusage,
+) -> HashMap<MeasItem, f64> {
+    // ru_maxrss is in bytes on macOS, but in kilobytes on the others.
+    #[cfg(target_os = "macos")]
+    const MAXRSS_UNIT: f64 = 1.0;
+    #[cfg(not(target_os = "macos"))]
+    const MAXRSS_UNIT: f64 = 1024.0;
+
+    let exit_status = if libc::WIFEXITED(status) {
+        libc::WEXITSTATUS(status)
+    } else if libc::WIFSIGNALED(status) {
+        // Same as the shell.
+        128 + libc::WTERMSIG(status)
+    } else {
+        status
+    };
+    let sec = |t: &libc::timeval| t.tv_sec as f64 + t.tv_usec as f64 / 1_000_000.0;
+    HashMap::from([
+        (MeasItem::ExitStatus, exit_status as f64),
+        (MeasItem::Real, elapsed),
+        (MeasItem::User, sec(&rusage.ru_utime)),
+        (MeasItem::Sys, sec(&rusage.ru_stime)),
+        (MeasItem::MaxResident, rusage.ru_maxrss as f64 * MAXRSS_UNIT),
+        (MeasItem::MajorPageFault, rusage.ru_majflt as f64),
+        (MeasItem::MinorPageFault, rusage.ru_minflt as f64),
+        (MeasItem::VoluntaryCtxSwitch, rusage.ru_nvcsw as f64),
+        (MeasItem::InvoluntaryCtxSwitch, rusage.ru_nivcsw as f64),
+        (MeasItem::Swap, rusage.ru_nswap as f64),
+        (MeasItem::BlockInput, rusage.ru_inblock as f64),
+        (MeasItem::BlockOutput, rusage.ru_oublock as f64),
+        (MeasItem::MsgSend, rusage.ru_msgsnd as f64),
+        (MeasItem::MsgRecv, rusage.ru_msgrcv as f64),
+        (MeasItem::SignalRecv, rusage.ru_nsignals as f64),
+    ])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn run(command: &str) -> HashMap<MeasItem, f64> {
+        run_polling(command, std::time::Duration::from_millis(1))
+    }
+
+    fn run_polling(command: &str, tick: std::time::Duration) -> HashMap<MeasItem, f64> {
+        let benchmark = crate::benchmark::Benchmark::new();
+        let mut cmd = RusageCmd::new(&benchmark);
+        assert_eq!(cmd.ready_status(), ReadyStatus::Ready);
+        cmd.execute(command, &ExecOptions::default()).unwrap();
+        while !cmd.is_finished() {
+            std::thread::sleep(tick);
+        }
+        cmd.get_report().unwrap().clone()
+    }
+
+    #[test]
+    fn rusage_measure() {
+        let report = run("sleep 0.1");
+        assert_eq!(report[&MeasItem::ExitStatus], 0.0);
+        assert!(0.1 <= report[&MeasItem::Real]);
+        assert!(0.0 < report[&MeasItem::MaxResident]);
+
+        let report = run("exit 3");
+        assert_eq!(report[&MeasItem::ExitStatus], 3.0);
+
+        let report = run("kill -KILL $$");
+        assert_eq!(report[&MeasItem::ExitStatus], 128.0 + 9.0);
+    }
+
+    #[test]
+    fn rusage_real_is_not_rounded_to_tick() {
+        let tick = std::time::Duration::from_millis(200);
+        let report = run_polling("true", tick);
+        assert!(report[&MeasItem::Real] < tick.as_secs_f64() / 4.0);
+    }
+
+    #[test]
+    fn rusage_kill() {
+        let benchmark = crate::benchmark::Benchmark::new();
+        let mut cmd = RusageCmd::new(&benchmark);
+        cmd.execute("sleep 10", &ExecOptions::default()).unwrap();
+        cmd.kill().unwrap();
+        assert!(!cmd.is_finished());
+    }
+}
@@ -0,0 +1,246 @@
+// Copyright © ArkBig
+//! This file provides the CPU affinity, nice value and scheduling policy of the benchmarked process.
+//!
+//! They are applied between fork and exec of the shell, so they are inherited by the time command
+//! and the benchmarked command, unlike `taskset` or `nice` that would be a part of the measured command.
+
+use crate::cmd::ExecOptions;
+
+/// Scheduling policy of [sched(7)][sched].
+///
+/// [sched]:https://man7.org/linux/man-pages/man7/sched.7.html
+#[derive(Debug, Copy, Clone, Eq, PartialEq, clap::ValueEnum)]
+pub enum SchedPolicy {
+    /// Real-time first-in first-out with the lowest priority. Needs root or CAP_SYS_NICE.
+    Fifo,
+    /// Real-time round-robin with the lowest priority. Needs root or CAP_SYS_NICE.
+    Rr,
+    /// For CPU-intensive batch processes, which are not preempted as often.
+    Batch,
+    /// For very low priority background jobs.
+    Idle,
+}
+
+#[derive(thiserror::Error, Debug)]
+enum SchedError {
+    #[error("Could not apply the CPU affinity, nice value or scheduling policy: {0}")]
+    Apply(std::io::Error),
+}
+
+/// Check if the settings of the options can be applied, by executing the shell with them.
+///
+/// If none are set, nothing is executed.
+pub fn check(shell: &str, shell_arg: &str, options: &ExecOptions) -> anyhow::Result<()> {
+    if !is_set(options) {
+        return Ok(());
+    }
+    let mut command = std::process::Command::new(shell);
+    command
+        .args([shell_arg, ":"])
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null());
+    apply(&mut command, options);
+    command
+        .spawn()
+        .and_then(|mut x| x.wait())
+        .map_err(SchedError::Apply)?;
+    Ok(())
+}
+
+/// True if any of the CPU affinity, nice value and scheduling policy is set.
+fn is_set(options: &ExecOptions) -> bool {
+    !options.cpu_affinity.is_empty() || options.nice.is_some() || options.sched.is_some()
+}
+
+/// Apply the settings of the options to the process before it starts.
+///
+/// If they cannot be applied, spawning the process fails.
+/// The CPU affinity and the scheduling policy are only on Linux, and the nice value is on Unix.
+pub fn apply(command: &mut std::process::Command, options: &ExecOptions) {
+    if !is_set(options) {
+        return;
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt as _;
+        let setting = Setting::new(options);
+        // SAFETY: Only async-signal-safe functions are called in the closure.
+        unsafe {
+            command.pre_exec(move || setting.apply());
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = command;
+    }
+}
+
+/// Settings prepared beforehand, because allocation is not safe after fork.
+#[cfg(unix)]
+struct Setting {
+    #[cfg(target_os = "linux")]
+    cpu_set: Option<libc::cpu_set_t>,
+    #[cfg(not(target_os = "linux"))]
+    cpu_affinity: bool,
+    nice: Option<i32>,
+    sched: Option<SchedPolicy>,
+}
+
+#[cfg(unix)]
+impl Setting {
+    fn new(options: &ExecOptions) -> Self {
+        Self {
+            #[cfg(target_os = "linux")]
+            cpu_set: (!options.cpu_affinity.is_empty()).then(|| {
+                // SAFETY: cpu_set_t is a plain C struct, so all zeros is a valid value.
+                let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+                for &cpu in &options.cpu_affinity {
+                    if cpu < max_cpus() {
+                        // SAFETY: cpu is within the set.
+                        unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+                    }
+                }
+                cpu_set
+            }),
+            #[cfg(not(target_os = "linux"))]
+            cpu_affinity: !options.cpu_affinity.is_empty(),
+            nice: options.nice,
+            sched: options.sched,
+        }
+    }
+
+    /// Apply to the current process. This is called in the child after fork.
+    fn apply(&self) -> std::io::Result<()> {
+        let check = |ret: libc::c_int| {
+            if ret == 0 {
+                Ok(())
+            } else {
+                Err(std::io::Error::last_os_error())
+            }
+        };
+        #[cfg(target_os = "linux")]
+        {
+            if let Some(cpu_set) = &self.cpu_set {
+                // SAFETY: cpu_set is valid during the call.
+                check(unsafe {
+                    libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), cpu_set)
+                })?;
+            }
+            if let Some(sched) = self.sched {
+                let policy = match sched {
+                    SchedPolicy::Fifo => libc::SCHED_FIFO,
+                    SchedPolicy::Rr => libc::SCHED_RR,
+                    SchedPolicy::Batch => libc::SCHED_BATCH,
+                    SchedPolicy::Idle => libc::SCHED_IDLE,
+                };
+                // SAFETY: param is valid during the call.
+                check(unsafe {
+                    let param = libc::sched_param {
+                        sched_priority: libc::sched_get_priority_min(policy),
+                    };
+                    libc::sched_setscheduler(0, policy, &param)
+                })?;
+            }
+        }
+        #[cfg(not(target_os = "linux"))]
+        if self.cpu_affinity || self.sched.is_some() {
+            return Err(std::io::ErrorKind::Unsupported.into());
+        }
+        if let Some(nice) = self.nice {
+            // SAFETY: setpriority has no preconditions.
+            check(unsafe { libc::setpriority(libc::PRIO_PROCESS, 0, nice) })?;
+        }
+        Ok(())
+    }
+}
+
+/// The number of CPUs that cpu_set_t can hold.
+#[cfg(target_os = "linux")]
+fn max_cpus() -> usize {
+    8 * std::mem::size_of::<libc::cpu_set_t>()
+}
+
+/// Parse the CPU list such as `0-3,6`, same as the list format of `taskset -c`.
+pub fn parse_cpu_list(s: &str) -> Result<Vec<usize>, String> {
+    let error = || format!("`{}` is not a CPU list such as 0-3,6", s);
+    let mut cpus = Vec::new();
+    for part in s.split(',') {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        let first: usize = first.trim().parse().map_err(|_| error())?;
+        let last: usize = last.trim().parse().map_err(|_| error())?;
+        if last < first {
+            return Err(error());
+        }
+        cpus.extend(first..=last);
+    }
+    cpus.sort_unstable();
+    cpus.dedup();
+    Ok(cpus)
+}
+
+/// Format the CPU list in the same format as parse_cpu_list().
+pub fn format_cpu_list(cpus: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                first.to_string()
+            } else {
+                format!("{}-{}", first, last)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn sched_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,6"), Ok(vec![0, 1, 2, 3, 6]));
+        assert_eq!(parse_cpu_list("6,2,2"), Ok(vec![2, 6]));
+        assert!(parse_cpu_list("3-0").is_err());
+        assert!(parse_cpu_list("a").is_err());
+        assert!(parse_cpu_list("").is_err());
+        assert_eq!(format_cpu_list(&[0, 1, 2, 3, 6]), "0-3,6");
+        assert_eq!(format_cpu_list(&[1, 3]), "1,3");
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn sched_apply() {
+        let options = ExecOptions {
+            cpu_affinity: vec![0],
+            nice: Some(5),
+            sched: Some(SchedPolicy::Batch),
+            ..Default::default()
+        };
+        let mut command = std::process::Command::new("sh");
+        command.args(["-c", "cat /proc/self/stat /proc/self/status"]);
+        apply(&mut command, &options);
+        let output = command.output().unwrap();
+        let output = String::from_utf8_lossy(&output.stdout);
+        // The 19th field of stat is nice, and the 41st is policy.
+        let stat: Vec<_> = output
+            .lines()
+            .next()
+            .unwrap()
+            .rsplit_once(") ")
+            .unwrap()
+            .1
+            .split(' ')
+            .collect();
+        assert_eq!(stat[19 - 3], "5");
+        assert_eq!(stat[41 - 3], libc::SCHED_BATCH.to_string());
+        assert!(output.contains("Cpus_allowed_list:\t0\n"));
+    }
+}
@@ -214,6 +214,39 @@ impl Stats {
             0.0
         }
     }
+
+    /// The q-quantile (0 ≦ q ≦ 1) of samples, by the same index as median() at 0.5.
+    pub fn quantile(&self, q: f64) -> f64 {
+        let count = self.sorted_samples.len();
+        let index = ((count as f64 * q) as usize).min(count.saturating_sub(1));
+        *self.sorted_samples.get(index).unwrap_or(&0.0)
+    }
+
+    /// Is the value out of the Hampel control limits?
+    pub fn is_outlier(&self, val: f64) -> bool {
+        val < self.lcl || self.ucl < val
+    }
+
+    /// Counts of the samples in the bins of equal width from min to max.
+    ///
+    /// The i-th bin is `[min + i * width, min + (i + 1) * width)`, and the last one includes max.
+    /// If all samples are the same, they are all in the first bin.
+    pub fn histogram(&self, bins: usize) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        if bins == 0 {
+            return counts;
+        }
+        let width = (self.max() - self.min()) / bins as f64;
+        for &x in &self.sorted_samples {
+            let index = if 0.0 < width {
+                ((x - self.min()) / width) as usize
+            } else {
+                0
+            };
+            counts[index.min(bins - 1)] += 1;
+        }
+        counts
+    }
 }
 
 fn sort_only_finite(data: &[f64]) -> Vec<f64> {
@@ -282,7 +315,7 @@ mod test {
         assert_eq!(stats.median(), 3.0);
         assert_eq!(stats.min(), 2.9);
         assert_eq!(stats.max(), 3.1);
-        assert_eq!(stats.has_outlier(), false);
+        assert!(!stats.has_outlier());
     }
 
     #[test]
@@ -306,7 +339,7 @@ mod test {
         assert_eq!(stats.median(), 3.0);
         assert_eq!(stats.min(), 0.0);
         assert_eq!(stats.max(), 10.0);
-        assert_eq!(stats.has_outlier(), true);
+        assert!(stats.has_outlier());
     }
 
     #[test]
@@ -330,7 +363,7 @@ mod test {
         assert_eq!(stats.median(), 3.0);
         assert_eq!(stats.min(), 0.0);
         assert_eq!(stats.max(), 10.0);
-        assert_eq!(stats.has_outlier(), true);
+        assert!(stats.has_outlier());
 
         stats.add(f64::INFINITY);
         assert_eq!(
@@ -357,7 +390,7 @@ mod test {
         assert_eq!(stats.median(), 3.0);
         assert_eq!(stats.min(), 0.0);
         assert_eq!(stats.max(), 10.0);
-        assert_eq!(stats.has_outlier(), true);
+        assert!(stats.has_outlier());
 
         stats.add(f64::NAN);
         assert_eq!(
@@ -410,7 +443,7 @@ mod test {
     fn empty_samples() {
         let samples = vec![];
         let stats = Stats::new(&samples);
-        assert_eq!(stats.sorted_samples, vec![]);
+        assert_eq!(stats.sorted_samples, Vec::<f64>::new());
         assert_eq!(stats.nan_count, 0);
         assert_ulps_eq!(stats.mad, 0.0);
         assert_eq!(stats.outlier_count, 0);
@@ -424,6 +457,19 @@ mod test {
         assert_eq!(stats.median(), 0.0);
         assert_eq!(stats.min(), 0.0);
         assert_eq!(stats.max(), 0.0);
-        assert_eq!(stats.has_outlier(), false);
+        assert!(!stats.has_outlier());
+    }
+
+    #[test]
+    fn stats_histogram() {
+        let stats = Stats::new(&[0.0, 3.0, 2.9, 3.1, 2.95, 3.05, 10.0]);
+        assert_eq!(stats.histogram(5), vec![1, 5, 0, 0, 1]);
+        assert_eq!(stats.histogram(0), Vec::<usize>::new());
+        assert_eq!(Stats::new(&[1.0, 1.0]).histogram(3), vec![2, 0, 0]);
+        assert!(stats.is_outlier(10.0));
+        assert_eq!(stats.quantile(0.5), stats.median());
+        assert_eq!(stats.quantile(0.25), 2.9);
+        assert_eq!(stats.quantile(1.0), 10.0);
+        assert!(!stats.is_outlier(3.0));
     }
 }
@@ -0,0 +1,182 @@
+// Copyright © ArkBig
+//! This file provides the state of the system that may disturb the measurement, such as other busy processes.
+//!
+//! It is captured before and after the runs of each command, and warned if unsuitable.
+//! The pressure is the "some avg10" of [PSI][psi], the percentage of time that some tasks were stalled.
+//!
+//! [psi]:https://docs.kernel.org/accounting/psi.html
+
+/// CPU pressure in percent above which other processes are competing for CPUs.
+const CPU_PRESSURE_LIMIT: f64 = 5.0;
+/// Memory pressure in percent above which the memory is short.
+const MEMORY_PRESSURE_LIMIT: f64 = 1.0;
+/// I/O pressure in percent above which other processes are competing for I/O.
+const IO_PRESSURE_LIMIT: f64 = 10.0;
+
+/// State of the system at one moment. Unavailable values are None or empty.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SystemState {
+    /// Load averages of 1, 5 and 15 minutes.
+    pub load_average: Option<[f64; 3]>,
+    pub online_cpus: usize,
+    /// Distinct CPU frequency scaling governors, such as "performance".
+    pub governors: Vec<String>,
+    /// True if turbo boost is enabled.
+    pub turbo: Option<bool>,
+    /// CPU pressure in percent.
+    pub cpu_pressure: Option<f64>,
+    /// Memory pressure in percent.
+    pub memory_pressure: Option<f64>,
+    /// I/O pressure in percent.
+    pub io_pressure: Option<f64>,
+}
+
+impl SystemState {
+    /// Capture the current state from /proc and /sys.
+    pub fn capture() -> Self {
+        let read = |path: &str| std::fs::read_to_string(path).ok();
+        let cpu_dir = "/sys/devices/system/cpu";
+        let mut governors: Vec<String> = std::fs::read_dir(cpu_dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|x| x.ok())
+            .filter(|x| x.file_name().to_string_lossy().starts_with("cpu"))
+            .filter_map(|x| std::fs::read_to_string(x.path().join("cpufreq/scaling_governor")).ok())
+            .map(|x| x.trim().to_string())
+            .collect();
+        governors.sort();
+        governors.dedup();
+        let turbo = match (
+            read(&format!("{}/intel_pstate/no_turbo", cpu_dir)),
+            read(&format!("{}/cpufreq/boost", cpu_dir)),
+        ) {
+            (Some(no_turbo), _) => Some(no_turbo.trim() == "0"),
+            (None, Some(boost)) => Some(boost.trim() == "1"),
+            (None, None) => None,
+        };
+        Self {
+            load_average: read("/proc/loadavg")
+                .and_then(|x| parse_load_average(&x))
+                .or_else(load_average),
+            online_cpus: read(&format!("{}/online", cpu_dir))
+                .and_then(|x| crate::sched::parse_cpu_list(x.trim()).ok())
+                .map(|x| x.len())
+                .or_else(|| std::thread::available_parallelism().ok().map(|x| x.get()))
+                .unwrap_or(1),
+            governors,
+            turbo,
+            cpu_pressure: read("/proc/pressure/cpu").and_then(|x| parse_pressure(&x)),
+            memory_pressure: read("/proc/pressure/memory").and_then(|x| parse_pressure(&x)),
+            io_pressure: read("/proc/pressure/io").and_then(|x| parse_pressure(&x)),
+        }
+    }
+
+    /// Warnings if the state is unsuitable for benchmarking.
+    ///
+    /// If before_runs, the load average and the CPU frequency settings are also checked.
+    /// They are not checked after the runs, because the benchmarked command itself raises the load average.
+    pub fn warnings(&self, before_runs: bool) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if before_runs {
+            if let Some([load, _, _]) = self.load_average {
+                if f64::max(1.0, self.online_cpus as f64 * 0.1) < load {
+                    warnings.push(format!("The load average is {:.2} with {} CPUs, so other processes may disturb the measurement.", load, self.online_cpus));
+                }
+            }
+            if let Some(governor) = self.governors.iter().find(|x| *x != "performance") {
+                warnings.push(format!("The CPU frequency scaling governor is `{}`, so the CPU frequency may vary. `performance` is recommended.", governor));
+            }
+            if self.turbo == Some(true) {
+                warnings.push(String::from(
+                    "Turbo boost is enabled, so the CPU frequency may vary with the temperature.",
+                ));
+            }
+        }
+        for (name, pressure, limit) in [
+            ("CPU", self.cpu_pressure, CPU_PRESSURE_LIMIT),
+            ("Memory", self.memory_pressure, MEMORY_PRESSURE_LIMIT),
+            ("I/O", self.io_pressure, IO_PRESSURE_LIMIT),
+        ] {
+            if let Some(pressure) = pressure.filter(|x| limit < *x) {
+                warnings.push(format!(
+                    "{} pressure is {:.1} %, so other processes may disturb the measurement.",
+                    name, pressure
+                ));
+            }
+        }
+        warnings
+    }
+}
+
+/// Load averages from the content of /proc/loadavg.
+fn parse_load_average(text: &str) -> Option<[f64; 3]> {
+    let mut values = text.split_whitespace().map(|x| x.parse().ok());
+    Some([values.next()??, values.next()??, values.next()??])
+}
+
+/// Load averages by getloadavg(3), for other than Linux.
+#[cfg(unix)]
+fn load_average() -> Option<[f64; 3]> {
+    let mut values = [0.0; 3];
+    // SAFETY: values has room for the 3 values.
+    (unsafe { libc::getloadavg(values.as_mut_ptr(), 3) } == 3).then_some(values)
+}
+
+#[cfg(not(unix))]
+fn load_average() -> Option<[f64; 3]> {
+    None
+}
+
+/// "some avg10" from the content of /proc/pressure/*.
+fn parse_pressure(text: &str) -> Option<f64> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("some "))?
+        .split_whitespace()
+        .find_map(|x| x.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn system_parse() {
+        assert_eq!(
+            parse_load_average("0.46 0.41 0.33 3/73 2720\n"),
+            Some([0.46, 0.41, 0.33])
+        );
+        assert_eq!(parse_load_average("0.46"), None);
+        let psi = "some avg10=3.17 avg60=6.83 avg300=5.89 total=134442942\n\
+                   full avg10=0.00 avg60=0.00 avg300=0.00 total=0\n";
+        assert_eq!(parse_pressure(psi), Some(3.17));
+        assert_eq!(parse_pressure(""), None);
+    }
+
+    #[test]
+    fn system_warnings() {
+        let quiet = SystemState {
+            load_average: Some([0.5, 0.5, 0.5]),
+            online_cpus: 4,
+            governors: vec![String::from("performance")],
+            turbo: Some(false),
+            cpu_pressure: Some(1.0),
+            memory_pressure: Some(0.0),
+            io_pressure: None,
+        };
+        assert!(quiet.warnings(true).is_empty());
+
+        let noisy = SystemState {
+            load_average: Some([3.0, 0.5, 0.5]),
+            governors: vec![String::from("performance"), String::from("powersave")],
+            turbo: Some(true),
+            cpu_pressure: Some(20.0),
+            ..quiet.clone()
+        };
+        assert_eq!(noisy.warnings(true).len(), 4);
+        // Only the pressure after the runs.
+        assert_eq!(noisy.warnings(false).len(), 1);
+        assert!(noisy.warnings(false)[0].starts_with("CPU pressure is 20.0 %"));
+    }
+}
@@ -9,3 +9,79 @@ pub fn mntime_raw_command() -> std::process::Command {
 pub fn mntime() -> assert_cmd::Command {
     assert_cmd::Command::from_std(mntime_raw_command())
 }
+
+/// Run mntime in a pseudo terminal, and type the keys once `ready` is printed.
+///
+/// The cursor position queries are answered as a terminal does.
+/// The exit code and the output are returned after it exits.
+#[cfg(unix)]
+#[allow(dead_code)]
+pub fn mntime_in_pty(args: &[&str], ready: &str, keys: &[u8]) -> (Option<i32>, String) {
+    use std::io::{Read as _, Write as _};
+    use std::os::fd::FromRawFd as _;
+    use std::os::unix::process::CommandExt as _;
+
+    let mut master = 0;
+    let mut slave = 0;
+    let size = libc::winsize {
+        ws_row: 40,
+        ws_col: 140,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    // SAFETY: The pointers are valid during the call, and the names and the termios are not needed.
+    let ret = unsafe {
+        libc::openpty(
+            &mut master,
+            &mut slave,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+            &size,
+        )
+    };
+    assert_eq!(ret, 0, "Could not open a pseudo terminal.");
+    // SAFETY: Both are opened by openpty and owned only here.
+    let (mut master, slave) = unsafe {
+        (
+            std::fs::File::from_raw_fd(master),
+            std::os::fd::OwnedFd::from_raw_fd(slave),
+        )
+    };
+
+    let mut cmd = mntime_raw_command();
+    cmd.args(args)
+        .stdin(slave.try_clone().unwrap())
+        .stdout(slave.try_clone().unwrap())
+        .stderr(slave);
+    // SAFETY: Only async-signal-safe functions are called in the closure.
+    unsafe {
+        cmd.pre_exec(|| {
+            // Make the pseudo terminal the controlling terminal.
+            libc::setsid();
+            libc::ioctl(0, libc::TIOCSCTTY, 0);
+            Ok(())
+        });
+    }
+    let mut child = cmd.spawn().unwrap();
+    // Close the slave of this side, so that reading the master ends when the child exits.
+    drop(cmd);
+
+    let mut writer = master.try_clone().unwrap();
+    let mut output = Vec::new();
+    let mut typed = false;
+    let mut buf = [0; 4096];
+    // It fails with EIO instead of EOF after the child closes the slave.
+    while let Ok(len @ 1..) = master.read(&mut buf) {
+        let chunk = &buf[..len];
+        output.extend_from_slice(chunk);
+        for _ in 0..chunk.windows(4).filter(|x| x == b"\x1b[6n").count() {
+            writer.write_all(b"\x1b[1;1R").unwrap();
+        }
+        if !typed && String::from_utf8_lossy(&output).contains(ready) {
+            writer.write_all(keys).unwrap();
+            typed = true;
+        }
+    }
+    let status = child.wait().unwrap();
+    (status.code(), String::from_utf8_lossy(&output).into_owned())
+}
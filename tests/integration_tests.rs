@@ -123,8 +123,412 @@ fn warns_about_missing_bsd_time_commands() {
         .arg("echo dummy benchmark")
         .assert()
         .success()
-        .stdout(predicates::str::contains("Percent of CPU this job got").not())
+        // Fallen back to built-in, which has no items only of the GNU and BSD time.
+        .stdout(predicates::str::contains("Average shared text size").not())
         .stderr(predicates::str::contains(
             "[WARNING]: The bsd time command not found.",
         ));
 }
+
+#[test]
+fn fails_without_any_backend() {
+    mntime()
+        .arg("--runs=1")
+        .arg("--backend=gnu")
+        .arg("--gnu=/this_will_never_exist")
+        .arg("--builtin=/this_will_never_exist")
+        .arg("echo dummy benchmark")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains(
+            "The GNU time `/this_will_never_exist` is not available. \
+            The built-in `/this_will_never_exist` could not be executed by `sh -c` nor bash.",
+        ));
+}
+
+#[test]
+fn command_label_is_supported() {
+    mntime()
+        .arg("--runs=1")
+        .arg("--name=first")
+        .arg("echo dummy benchmark")
+        .arg("echo dummy benchmark 2")
+        .assert()
+        .success()
+        .stdout(
+            predicates::str::contains("Benchmark #1> first").and(predicates::str::contains(
+                "Benchmark #2> echo dummy benchmark 2",
+            )),
+        );
+}
+
+#[test]
+fn environment_variable_is_supported() {
+    mntime()
+        .arg("--runs=1")
+        .arg("--env=MNTIME_TEST=global")
+        .arg("--command-env=2:MNTIME_TEST=local")
+        .arg("test \"$MNTIME_TEST\" = global")
+        .arg("test \"$MNTIME_TEST\" = local")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Exit status").not());
+}
+
+#[test]
+fn working_directory_is_supported() {
+    mntime()
+        .arg("--runs=1")
+        .arg("--cwd=..")
+        .arg("--command-cwd=2:.")
+        .arg("test -f Cargo.toml")
+        .arg("test -f common.rs")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Exit status").not());
+}
+
+#[test]
+fn export_json_and_csv_is_supported() {
+    let dir = std::env::temp_dir().join(format!("mntime_export_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let json = dir.join("result.json");
+    let csv = dir.join("result.csv");
+    mntime()
+        .arg("--runs=2")
+        .arg(format!("--export-json={}", json.display()))
+        .arg(format!("--export-csv={}", csv.display()))
+        .arg("--name=dummy")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Benchmark #1> dummy"))
+        .stdout(predicates::str::contains("Metadata> mntime "));
+    let json = std::fs::read_to_string(json).unwrap();
+    assert!(json.contains("\"name\": \"dummy\""));
+    assert!(json.contains("\"Real\""));
+    assert!(json.contains("\"metadata\""));
+    let csv = std::fs::read_to_string(csv).unwrap();
+    // The metadata comments precede the header.
+    assert!(csv.starts_with("# version: "));
+    assert!(csv.contains("\ncommand_index,name,backend,run,item,value\n"));
+    assert!(csv.contains("\n1,dummy,"));
+    assert!(csv.contains(",2,Real,"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn quitting_reports_on_interrupt() {
+    let dir = std::env::temp_dir().join(format!("mntime_interrupt_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let json = dir.join("result.json");
+    let json_arg = format!("--export-json={}", json.display());
+    let csv = dir.join("samples.csv");
+    let csv_arg = format!("--export-csv={}", csv.display());
+    let args = ["--runs=100", "sleep 0.2", "echo never"];
+
+    // Quit without reporting.
+    let (code, output) = common::mntime_in_pty(&args, "Benchmark #1>", b"q");
+    assert_eq!(code, Some(1));
+    assert!(!output.contains("Interrupted"));
+
+    // Quit while the first command is measured.
+    let args = [
+        &["--report-on-interrupt", json_arg.as_str(), csv_arg.as_str()],
+        &args[..],
+    ]
+    .concat();
+    let (code, output) = common::mntime_in_pty(&args, "Benchmark #1>", b"q");
+    // Same as a shell for SIGINT.
+    assert_eq!(code, Some(130));
+    assert!(output.contains("Interrupted, so the remaining commands are not run"));
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&json).unwrap()).unwrap();
+    assert_eq!(json["interrupted"], true);
+    let commands = json["commands"].as_array().unwrap();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0]["partial"], true);
+    let csv = std::fs::read_to_string(csv).unwrap();
+    assert!(csv.ends_with("# partial: 1,sleep 0.2\n# interrupted: true\n"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn export_html_is_supported() {
+    let dir = std::env::temp_dir().join(format!("mntime_export_html_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let html = dir.join("report.html");
+    mntime()
+        .arg("--runs=3")
+        .arg(format!("--export-html={}", html.display()))
+        .arg("--name=<dummy>")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success();
+    let html = std::fs::read_to_string(html).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("&lt;dummy&gt;"));
+    assert!(html.contains("<svg "));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn export_gnuplot_and_vega_is_supported() {
+    let dir = std::env::temp_dir().join(format!("mntime_export_plot_{}", std::process::id()));
+    let vega = dir.join("spec.vl.json");
+    mntime()
+        .arg("--runs=2")
+        .arg(format!(
+            "--export-gnuplot={}",
+            dir.join("gnuplot").display()
+        ))
+        .arg(format!("--export-vega={}", vega.display()))
+        .arg("echo dummy benchmark")
+        .assert()
+        .success();
+    let data = std::fs::read_to_string(dir.join("gnuplot/Real.dat")).unwrap();
+    assert!(data.contains("\n# run value\n1 "));
+    let script = std::fs::read_to_string(dir.join("gnuplot/Real.gp")).unwrap();
+    assert!(script.contains("'Real.dat' index 0"));
+    let vega = std::fs::read_to_string(vega).unwrap();
+    assert!(vega.contains("\"$schema\": \"https://vega.github.io/schema/vega-lite/"));
+    assert!(vega.contains("\"item\": \"Real\""));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn hyperfine_json_is_exported_and_imported() {
+    let dir = std::env::temp_dir().join(format!("mntime_hyperfine_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let exported = dir.join("exported.json");
+    mntime()
+        .arg("--runs=2")
+        .arg(format!("--export-hyperfine-json={}", exported.display()))
+        .arg("--name=dummy")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success();
+    let json = std::fs::read_to_string(&exported).unwrap();
+    assert!(json.contains("\"results\""));
+    assert!(json.contains("\"command\": \"dummy\""));
+    assert!(json.contains("\"exit_codes\""));
+
+    let csv = dir.join("imported.csv");
+    mntime()
+        .arg(format!("--export-csv={}", csv.display()))
+        .arg("import")
+        .arg(&exported)
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Import> "))
+        .stdout(predicates::str::contains("Benchmark #1> dummy"))
+        .stdout(predicates::str::contains("Elapsed (wall clock) time"));
+    let csv = std::fs::read_to_string(csv).unwrap();
+    assert!(csv.contains("\n1,dummy,hyperfine,2,Real,"));
+
+    mntime()
+        .arg("import")
+        .arg(dir.join("not_found.json"))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Could not read the import file"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn native_backend_is_supported() {
+    mntime()
+        .arg("--runs=2")
+        .arg("--backend=native")
+        .arg("--no-system-check")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Maximum resident set size"))
+        .stderr(predicates::str::contains("[WARNING]").not());
+}
+
+#[test]
+fn all_alternating_backend_reports_separately() {
+    mntime()
+        .arg("--runs=2")
+        .arg("--backend=all-alternating")
+        .arg("--bsd=/this_will_never_exist")
+        .arg("--busybox=/this_will_never_exist")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success()
+        .stderr(predicates::str::contains(
+            "[WARNING]: The bsd time command not found.",
+        ))
+        .stderr(predicates::str::contains(
+            "[WARNING]: The busybox time command not found.",
+        ));
+}
+
+#[test]
+fn custom_metric_is_supported() {
+    mntime()
+        .arg("--runs=2")
+        .arg("--metric=parse:duration=parse: (\\d+ms)")
+        .arg("--metric=rate=items/s: ([\\d.]+)")
+        .arg("echo parse: 20ms; echo items/s: 4567 >&2")
+        .assert()
+        .success()
+        .stdout(predicates::str::is_match(r"parse +: +20 ms").unwrap())
+        .stdout(predicates::str::is_match(r"rate +: +4,567 ").unwrap());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn memory_sampling_is_supported() {
+    mntime()
+        .arg("--runs=2")
+        .arg("--backend=native")
+        .arg("--sample-memory")
+        .arg("sleep 0.2")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Mean resident set size"))
+        .stdout(predicates::str::contains("Time to peak resident set size"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn process_tree_is_supported() {
+    mntime()
+        .arg("--runs=2")
+        .arg("--backend=native")
+        .arg("--process-tree")
+        .arg("sleep 0.2 & sleep 0.2 & wait")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Peak RSS of the process tree"))
+        .stdout(predicates::str::is_match(r"\[sleep\] processes +: +2 ").unwrap());
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn cgroup_falls_back_without_limits() {
+    // The cgroup may not be writable, but it runs anyway.
+    mntime()
+        .arg("--runs=2")
+        .arg("--backend=native")
+        .arg("--cgroup")
+        .arg("sleep 0.1")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Elapsed (wall clock) time"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn perf_counters_degrade_gracefully() {
+    // Hardware counters may not be permitted or supported, but it runs anyway.
+    mntime()
+        .arg("--runs=2")
+        .arg("--backend=native")
+        .arg("--perf-counters")
+        .arg("seq 1000")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Elapsed (wall clock) time"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn proc_io_is_supported() {
+    mntime()
+        .arg("--runs=2")
+        .arg("--backend=native")
+        .arg("--proc-io")
+        .arg("head -c 100000 /dev/zero > /dev/null")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Bytes read by syscalls"))
+        .stdout(predicates::str::contains("Read syscalls"));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn process_settings_are_applied() {
+    mntime()
+        .arg("--runs=1")
+        .arg("--cpu-affinity=0")
+        .arg("--nice=3")
+        .arg("--sched=batch")
+        .arg("true")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "Settings: --cpu-affinity 0 --nice 3 --sched batch",
+        ));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn cold_cache_is_supported() {
+    // Evicting the page cache of the files does not need root.
+    mntime()
+        .arg("--runs=2")
+        .arg("--cold-cache=common.rs")
+        .arg("--cold-cache=.")
+        .arg("cat common.rs")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "Settings: --cold-cache common.rs --cold-cache .",
+        ))
+        .stderr(predicates::str::contains("Could not evict").not());
+    // Others are warned, and it continues without them.
+    mntime()
+        .arg("--runs=1")
+        .arg("--cold-cache=/this_will_never_exist")
+        .arg("--drop-caches")
+        .arg("true")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("--cold-cache /this_will_never_exist").not())
+        .stderr(predicates::str::contains(
+            "[WARNING]: Could not evict the page cache of `/this_will_never_exist`",
+        ));
+}
+
+#[test]
+fn tui_is_ignored_without_terminal() {
+    mntime()
+        .arg("--runs=1")
+        .arg("--tui")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Benchmark #1>"))
+        .stderr(predicates::str::contains(
+            "[WARNING]: `--tui` is ignored, because stdin or stdout is not a terminal.",
+        ));
+}
+
+#[test]
+fn histogram_is_printed() {
+    mntime()
+        .arg("--runs=4")
+        .arg("--histogram=Real,Unknown")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("Elapsed (wall clock) time:\r\n"))
+        .stdout(predicates::str::contains("LCL "));
+    // Without ITEMS, the next argument is the command.
+    mntime()
+        .arg("--runs=2")
+        .arg("--histogram")
+        .arg("echo dummy benchmark")
+        .assert()
+        .success()
+        .stdout(predicates::str::contains(
+            "Benchmark #1> echo dummy benchmark",
+        ))
+        .stdout(predicates::str::contains("Elapsed (wall clock) time:\r\n"));
+}
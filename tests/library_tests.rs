@@ -0,0 +1,156 @@
+use mntime_lib::{Backend, Benchmark, BenchmarkCommand, MeasItem};
+
+#[test]
+fn benchmark_runs_successfully() {
+    let result = Benchmark::new()
+        .command("echo dummy benchmark")
+        .command(BenchmarkCommand::new("echo dummy benchmark 2").name("second"))
+        .runs(2)
+        .run()
+        .unwrap();
+    assert_eq!(result.commands.len(), 2);
+    assert_eq!(result.commands[0].name, "echo dummy benchmark");
+    assert_eq!(result.commands[1].name, "second");
+    assert_eq!(result.commands[1].command, "echo dummy benchmark 2");
+    for command in &result.commands {
+        assert_eq!(command.runs.len(), 2);
+        assert_eq!(command.stats(&MeasItem::Real).count(), 2);
+    }
+}
+
+#[test]
+fn benchmark_backend_is_supported() {
+    let result = Benchmark::new()
+        .command("echo dummy benchmark")
+        .runs(1)
+        .loops(2)
+        .backend(Backend::Builtin)
+        .run()
+        .unwrap();
+    let command = &result.commands[0];
+    assert_eq!(command.loops, 2);
+    assert!(command.items().contains(&MeasItem::Real));
+    assert!(!command.items().contains(&MeasItem::MinorPageFault));
+}
+
+#[cfg(unix)]
+#[test]
+fn native_backend_is_supported() {
+    let result = Benchmark::new()
+        .command("echo dummy benchmark")
+        .runs(2)
+        .backend(Backend::Native)
+        .run()
+        .unwrap();
+    let command = &result.commands[0];
+    assert_eq!(command.backends, vec!["native", "native"]);
+    assert!(command.items().contains(&MeasItem::MaxResident));
+    let metadata = result.metadata.unwrap();
+    assert_eq!(metadata.backends, vec![(String::from("native"), None)]);
+    assert_eq!(metadata.runs, 2);
+    assert_eq!(metadata.shell, "sh -c");
+}
+
+#[test]
+fn custom_reporter_is_supported() {
+    #[derive(Default)]
+    struct CountReporter {
+        events: Vec<String>,
+    }
+    impl mntime_lib::Reporter for CountReporter {
+        fn benchmark_start(
+            &mut self,
+            index: usize,
+            _command: &BenchmarkCommand,
+        ) -> anyhow::Result<()> {
+            self.events.push(format!("start {}", index));
+            Ok(())
+        }
+        fn run_finished(
+            &mut self,
+            index: usize,
+            n: u16,
+            _backend: &str,
+            _report: &std::collections::HashMap<MeasItem, f64>,
+        ) -> anyhow::Result<()> {
+            self.events.push(format!("run {} {}", index, n));
+            Ok(())
+        }
+        fn command_finished(
+            &mut self,
+            index: usize,
+            _result: &mntime_lib::CommandResult,
+        ) -> anyhow::Result<()> {
+            self.events.push(format!("finished {}", index));
+            Ok(())
+        }
+        fn all_finished(&mut self, result: &mntime_lib::BenchmarkResult) -> anyhow::Result<()> {
+            self.events.push(format!("all {}", result.commands.len()));
+            Ok(())
+        }
+    }
+
+    let mut reporter = CountReporter::default();
+    Benchmark::new()
+        .command("echo dummy benchmark")
+        .runs(2)
+        .backend(Backend::Builtin)
+        .run_with(&mut reporter)
+        .unwrap();
+    assert_eq!(
+        reporter.events,
+        vec!["start 0", "run 0 0", "run 0 1", "finished 0", "all 1"]
+    );
+}
+
+#[test]
+fn custom_backend_is_supported() {
+    struct MockBackend {
+        count: f64,
+        report: std::collections::HashMap<MeasItem, f64>,
+    }
+    impl mntime_lib::MeasureBackend for MockBackend {
+        fn name(&self) -> &str {
+            "mock"
+        }
+        fn ready_status(&mut self) -> mntime_lib::ReadyStatus {
+            mntime_lib::ReadyStatus::Ready
+        }
+        fn execute(
+            &mut self,
+            _command: &str,
+            _options: &mntime_lib::ExecOptions,
+        ) -> anyhow::Result<()> {
+            self.count += 1.0;
+            self.report = std::collections::HashMap::from([
+                (MeasItem::ExitStatus, 0.0),
+                (MeasItem::Real, self.count),
+            ]);
+            Ok(())
+        }
+        fn is_finished(&mut self) -> bool {
+            true
+        }
+        fn get_report(&mut self) -> anyhow::Result<&std::collections::HashMap<MeasItem, f64>> {
+            Ok(&self.report)
+        }
+        fn kill(&mut self) -> anyhow::Result<()> {
+            Ok(())
+        }
+    }
+
+    let result = Benchmark::new()
+        .command("never executed")
+        .runs(3)
+        .run_with_backends(
+            vec![Box::new(MockBackend {
+                count: 0.0,
+                report: Default::default(),
+            })],
+            &mut Vec::new(),
+        )
+        .unwrap();
+    let stats = result.commands[0].stats(&MeasItem::Real);
+    assert_eq!(stats.count(), 3);
+    assert_eq!(stats.mean, 2.0);
+}